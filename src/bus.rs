@@ -0,0 +1,27 @@
+pub mod dma;
+pub mod flat;
+pub mod memory_map;
+
+pub use dma::OamDma;
+pub use flat::FlatRam;
+pub use memory_map::MemoryMap;
+
+/// Everything the CPU can see through its address pins. Reads take `&mut self`
+/// because on real hardware reading some registers has side effects, and so
+/// that test buses can record every access.
+pub trait Bus {
+    fn read(&mut self, address: u16) -> u8;
+    fn write(&mut self, address: u16, value: u8);
+
+    /// Advances everything else attached to the bus (timer, PPU, ...) by one
+    /// M-cycle. The CPU calls this once for every M-cycle it spends, right
+    /// before any memory access made in that cycle.
+    fn tick(&mut self) {}
+
+    /// Advances by one M-cycle while the CPU is in STOP. Everything but the
+    /// joypad is halted then, so by default nothing happens.
+    fn tick_stopped(&mut self) {}
+
+    /// The CPU has executed STOP, which also resets the divider.
+    fn enter_stop(&mut self) {}
+}
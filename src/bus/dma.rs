@@ -0,0 +1,84 @@
+use crate::ppu::OAM_SIZE;
+
+pub const DMA_ADDRESS: u16 = 0xFF46;
+
+// A transfer in progress: where it's copying from and how many bytes are done.
+#[derive(Clone, Copy)]
+struct Transfer {
+    source: u16,
+    copied: usize,
+}
+
+/// The OAM DMA controller. Writing a page number to FF46 copies the 160
+/// bytes starting at that page into OAM, one byte per M-cycle. The copy starts
+/// one M-cycle after the write and keeps the bus busy for 160 M-cycles.
+///
+/// Writing FF46 again while a copy is running restarts it from the new page;
+/// the old copy carries on until the new one takes over.
+pub struct OamDma {
+    register: u8,
+    // A requested transfer and how many M-cycles are left before it starts.
+    starting: Option<(Transfer, u8)>,
+    running: Option<Transfer>,
+}
+
+impl OamDma {
+    pub fn new() -> OamDma {
+        OamDma {
+            register: 0xFF,
+            starting: None,
+            running: None,
+        }
+    }
+
+    /// FF46 reads back the last page written to it.
+    pub fn read(&self) -> u8 {
+        self.register
+    }
+
+    pub fn write(&mut self, value: u8) {
+        self.register = value;
+        let transfer = Transfer {
+            source: (value as u16) << 8,
+            copied: 0,
+        };
+        self.starting = Some((transfer, 1));
+    }
+
+    /// The start of the page being copied while a transfer holds the bus.
+    pub fn source(&self) -> Option<u16> {
+        self.running.map(|transfer| transfer.source)
+    }
+
+    pub fn active(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Advances by one M-cycle, returning the address to copy from and the
+    /// OAM offset to copy to in this cycle, if any.
+    pub fn tick(&mut self) -> Option<(u16, usize)> {
+        match self.starting {
+            Some((transfer, 0)) => {
+                self.starting = None;
+                self.running = Some(transfer);
+            }
+            Some((transfer, delay)) => self.starting = Some((transfer, delay - 1)),
+            None => {}
+        }
+
+        let transfer = self.running.as_mut()?;
+        if transfer.copied == OAM_SIZE {
+            self.running = None;
+            return None;
+        }
+        let copy = (transfer.source + transfer.copied as u16, transfer.copied);
+        transfer.copied += 1;
+        Some(copy)
+    }
+}
+
+impl Default for OamDma {
+    fn default() -> Self {
+        OamDma::new()
+    }
+}
@@ -0,0 +1,37 @@
+use crate::bus::Bus;
+
+/// 64 KiB of plain read/write memory with no mapping or side effects.
+/// Mostly useful for tests.
+pub struct FlatRam {
+    pub memory: Box<[u8; 0x10000]>,
+}
+
+impl FlatRam {
+    pub fn new() -> FlatRam {
+        FlatRam {
+            memory: Box::new([0; 0x10000]),
+        }
+    }
+
+    /// Copies `bytes` into memory starting at `address`.
+    pub fn load(&mut self, address: u16, bytes: &[u8]) {
+        let start = address as usize;
+        self.memory[start..start + bytes.len()].copy_from_slice(bytes);
+    }
+}
+
+impl Default for FlatRam {
+    fn default() -> Self {
+        FlatRam::new()
+    }
+}
+
+impl Bus for FlatRam {
+    fn read(&mut self, address: u16) -> u8 {
+        self.memory[address as usize]
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        self.memory[address as usize] = value;
+    }
+}
@@ -0,0 +1,169 @@
+use crate::bus::{Bus, OamDma};
+use crate::cartridge::Mapper;
+use crate::interrupts::{Interrupt, INTERRUPT_MASK};
+use crate::joypad::Joypad;
+use crate::ppu::Ppu;
+use crate::serial::Serial;
+use crate::timer::{Timer, DIV_ADDRESS};
+
+const WORK_RAM_SIZE: usize = 0x2000;
+const IO_SIZE: usize = 0x80;
+const HIGH_RAM_SIZE: usize = 0x7F;
+
+/// The DMG address space:
+///
+/// | range       | contents                              |
+/// |-------------|---------------------------------------|
+/// | 0000-7FFF   | cartridge ROM                         |
+/// | 8000-9FFF   | video RAM                             |
+/// | A000-BFFF   | external (cartridge) RAM              |
+/// | C000-DFFF   | work RAM                              |
+/// | E000-FDFF   | echo of C000-DDFF                     |
+/// | FE00-FE9F   | object attribute memory               |
+/// | FEA0-FEFF   | not usable                            |
+/// | FF00-FF7F   | I/O registers                         |
+/// | FF80-FFFE   | high RAM                              |
+/// | FFFF        | interrupt enable register             |
+///
+/// While an OAM DMA is running the CPU can't reach OAM (reads give 0xFF), and
+/// reads from whichever bus the DMA is copying from (VRAM or everything else
+/// below FE00) give the byte being copied instead. I/O and HRAM stay
+/// reachable, which is why games run their DMA wait loop from HRAM.
+pub struct MemoryMap {
+    pub cartridge: Box<dyn Mapper>,
+    pub ppu: Ppu,
+    pub dma: OamDma,
+    pub joypad: Joypad,
+    pub serial: Serial,
+    pub timer: Timer,
+    dma_byte: u8,
+    wram: [u8; WORK_RAM_SIZE],
+    io: [u8; IO_SIZE],
+    hram: [u8; HIGH_RAM_SIZE],
+    interrupt_flag: u8,
+    interrupt_enable: u8,
+}
+
+impl MemoryMap {
+    pub fn new(cartridge: Box<dyn Mapper>) -> MemoryMap {
+        MemoryMap {
+            cartridge,
+            ppu: Ppu::new(),
+            dma: OamDma::new(),
+            joypad: Joypad::new(),
+            serial: Serial::new(),
+            timer: Timer::new(),
+            dma_byte: 0xFF,
+            wram: [0; WORK_RAM_SIZE],
+            io: [0; IO_SIZE],
+            hram: [0; HIGH_RAM_SIZE],
+            interrupt_flag: 0,
+            interrupt_enable: 0,
+        }
+    }
+
+    /// Sets `interrupt`'s bit in IF. This is how peripherals on the bus raise
+    /// interrupts; the CPU picks them up before its next instruction.
+    pub fn request_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupt_flag |= interrupt.mask();
+    }
+
+    // What the CPU sees instead of `address` while a DMA holds the bus, or
+    // None if the access goes through.
+    fn dma_conflict(&self, address: u16) -> Option<u8> {
+        let source = self.dma.source()?;
+        let on_vram_bus = |address| (0x8000..=0x9FFF).contains(&address);
+        match address {
+            0xFE00..=0xFEFF => Some(0xFF),
+            0xFF00..=0xFFFF => None,
+            _ if on_vram_bus(address) == on_vram_bus(source) => Some(self.dma_byte),
+            _ => None,
+        }
+    }
+
+    // DMA reads bypass the PPU's lockout. Pages above DFFF read work RAM.
+    fn dma_read(&mut self, address: u16) -> u8 {
+        match address {
+            0x0000..=0x7FFF => self.cartridge.read_rom(address),
+            0x8000..=0x9FFF => self.ppu.state.vram[address as usize - 0x8000],
+            0xA000..=0xBFFF => self.cartridge.read_ram(address - 0xA000),
+            _ => self.wram[address as usize & (WORK_RAM_SIZE - 1)],
+        }
+    }
+}
+
+impl Bus for MemoryMap {
+    fn read(&mut self, address: u16) -> u8 {
+        if let Some(value) = self.dma_conflict(address) {
+            return value;
+        }
+        let address = address as usize;
+        match address {
+            0x0000..=0x7FFF => self.cartridge.read_rom(address as u16),
+            0x8000..=0x9FFF => self.ppu.read(address as u16),
+            0xA000..=0xBFFF => self.cartridge.read_ram((address - 0xA000) as u16),
+            0xC000..=0xDFFF => self.wram[address - 0xC000],
+            0xE000..=0xFDFF => self.wram[address - 0xE000],
+            0xFE00..=0xFE9F => self.ppu.read(address as u16),
+            0xFEA0..=0xFEFF => 0xFF,
+            0xFF00 => self.joypad.read(),
+            0xFF01..=0xFF02 => self.serial.read(address as u16),
+            0xFF04..=0xFF07 => self.timer.read(address as u16),
+            // the unused upper bits of IF always read as 1
+            0xFF0F => self.interrupt_flag | !INTERRUPT_MASK,
+            0xFF46 => self.dma.read(),
+            0xFF40..=0xFF45 | 0xFF47..=0xFF4B => self.ppu.read(address as u16),
+            0xFF03..=0xFF7F => self.io[address - 0xFF00],
+            0xFF80..=0xFFFE => self.hram[address - 0xFF80],
+            _ => self.interrupt_enable,
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        if self.dma_conflict(address).is_some() {
+            return;
+        }
+        let address = address as usize;
+        match address {
+            0x0000..=0x7FFF => self.cartridge.write_rom(address as u16, value),
+            0x8000..=0x9FFF => self.ppu.write(address as u16, value),
+            0xA000..=0xBFFF => self.cartridge.write_ram((address - 0xA000) as u16, value),
+            0xC000..=0xDFFF => self.wram[address - 0xC000] = value,
+            0xE000..=0xFDFF => self.wram[address - 0xE000] = value,
+            0xFE00..=0xFE9F => self.ppu.write(address as u16, value),
+            0xFEA0..=0xFEFF => {}
+            0xFF00 => self.joypad.write(value),
+            0xFF01..=0xFF02 => self.serial.write(address as u16, value),
+            0xFF04..=0xFF07 => self.timer.write(address as u16, value),
+            0xFF0F => self.interrupt_flag = value & INTERRUPT_MASK,
+            0xFF46 => self.dma.write(value),
+            0xFF40..=0xFF45 | 0xFF47..=0xFF4B => self.ppu.write(address as u16, value),
+            0xFF03..=0xFF7F => self.io[address - 0xFF00] = value,
+            0xFF80..=0xFFFE => self.hram[address - 0xFF80] = value,
+            _ => self.interrupt_enable = value,
+        }
+    }
+
+    // The PPU runs at four dots per M-cycle.
+    fn tick(&mut self) {
+        self.interrupt_flag |= self.timer.tick();
+        self.interrupt_flag |= self.joypad.tick();
+        self.interrupt_flag |= self.serial.tick();
+        if let Some((source, offset)) = self.dma.tick() {
+            self.dma_byte = self.dma_read(source);
+            self.ppu.state.oam[offset] = self.dma_byte;
+        }
+        for _ in 0..4 {
+            self.interrupt_flag |= self.ppu.tick();
+        }
+    }
+
+    // Only the joypad keeps running in STOP, since it's what wakes the CPU.
+    fn tick_stopped(&mut self) {
+        self.interrupt_flag |= self.joypad.tick();
+    }
+
+    fn enter_stop(&mut self) {
+        self.timer.write(DIV_ADDRESS, 0);
+    }
+}
@@ -0,0 +1,237 @@
+pub mod camera;
+pub mod header;
+pub mod huc1;
+pub mod huc3;
+pub mod mbc1;
+pub mod mbc2;
+pub mod mbc3;
+pub mod mbc5;
+pub mod mbc6;
+pub mod mbc7;
+pub mod mmm01;
+pub mod rom_only;
+pub mod rtc;
+pub mod save_file;
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+pub use camera::{ImageSensor, PocketCamera};
+pub use header::{CgbSupport, Header, Licensee, MapperKind};
+pub use huc1::HuC1;
+pub use huc3::HuC3;
+pub use mbc1::Mbc1;
+pub use mbc2::Mbc2;
+pub use mbc3::Mbc3;
+pub use mbc5::Mbc5;
+pub use mbc6::Mbc6;
+pub use mbc7::{Accelerometer, Mbc7};
+pub use mmm01::Mmm01;
+pub use rom_only::RomOnly;
+pub use rtc::{Clock, SystemClock, VirtualClock};
+pub use save_file::SaveFile;
+
+pub const ROM_BANK_SIZE: usize = 0x4000;
+pub const RAM_BANK_SIZE: usize = 0x2000;
+
+/// Called with `true` when a cartridge's rumble motor starts and `false` when
+/// it stops.
+pub type RumbleHandler = Box<dyn FnMut(bool)>;
+
+/// The other end of the infrared link on HuC1 and HuC3 cartridges.
+pub trait InfraredPort {
+    /// The cartridge turned its LED on or off.
+    fn set_led(&mut self, on: bool);
+    /// Whether the cartridge's sensor currently sees light.
+    fn light_detected(&mut self) -> bool;
+}
+
+// The byte at `address` within `bank`, wrapping banks past the end of `data`
+// since the unused bank lines aren't connected.
+pub(crate) fn banked_byte(data: &[u8], bank_size: usize, bank: usize, address: u16) -> u8 {
+    let banks = (data.len() / bank_size).max(1);
+    let offset = (bank % banks) * bank_size + (address as usize & (bank_size - 1));
+    data.get(offset).copied().unwrap_or(0xFF)
+}
+
+/// A memory bank controller, which decides what the CPU sees at 0000-7FFF and
+/// A000-BFFF. Writes to the ROM area go to the controller's registers.
+/// Addresses passed to the RAM methods are relative to 0xA000.
+pub trait Mapper {
+    fn read_rom(&mut self, address: u16) -> u8;
+    fn write_rom(&mut self, address: u16, value: u8);
+    fn read_ram(&mut self, address: u16) -> u8;
+    fn write_ram(&mut self, address: u16, value: u8);
+
+    /// Everything a battery keeps alive (external RAM, clock state) in the
+    /// layout it is saved to disk in.
+    fn save_data(&mut self) -> Vec<u8> {
+        Vec::new()
+    }
+
+    /// Restores what `save_data` returned.
+    fn load_save_data(&mut self, _data: &[u8]) {}
+
+    /// Writes out battery-backed memory, for mappers that keep it somewhere;
+    /// see `SaveFile`.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Only cartridges with a rumble motor ever call `handler`.
+    fn set_rumble_handler(&mut self, _handler: RumbleHandler) {}
+
+    /// Ignored by cartridges without an infrared LED and sensor.
+    fn set_infrared_port(&mut self, _port: Box<dyn InfraredPort>) {}
+
+    /// Ignored by cartridges without a tilt sensor.
+    fn set_accelerometer(&mut self, _accelerometer: Box<dyn Accelerometer>) {}
+
+    /// Ignored by cartridges without a camera.
+    fn set_image_sensor(&mut self, _sensor: Box<dyn ImageSensor>) {}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CartridgeError {
+    TooSmall(usize),
+    UnknownCartridgeType(u8),
+    UnknownRomSize(u8),
+    UnknownRamSize(u8),
+    RomSizeMismatch { header: usize, actual: usize },
+    BadLogo,
+    HeaderChecksum { expected: u8, actual: u8 },
+    GlobalChecksum { expected: u16, actual: u16 },
+    UnsupportedCartridgeType(u8),
+}
+
+impl fmt::Display for CartridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CartridgeError::TooSmall(size) => {
+                write!(f, "{} bytes is too small to hold a cartridge header", size)
+            }
+            CartridgeError::UnknownCartridgeType(code) => {
+                write!(f, "unknown cartridge type {:#04X}", code)
+            }
+            CartridgeError::UnknownRomSize(code) => write!(f, "unknown ROM size {:#04X}", code),
+            CartridgeError::UnknownRamSize(code) => write!(f, "unknown RAM size {:#04X}", code),
+            CartridgeError::RomSizeMismatch { header, actual } => write!(
+                f,
+                "header says the ROM is {} bytes but the image is {} bytes",
+                header, actual
+            ),
+            CartridgeError::BadLogo => write!(f, "Nintendo logo doesn't match"),
+            CartridgeError::HeaderChecksum { expected, actual } => write!(
+                f,
+                "header checksum is {:#04X} but the header says {:#04X}",
+                actual, expected
+            ),
+            CartridgeError::GlobalChecksum { expected, actual } => write!(
+                f,
+                "global checksum is {:#06X} but the header says {:#06X}",
+                actual, expected
+            ),
+            CartridgeError::UnsupportedCartridgeType(code) => {
+                write!(f, "cartridge type {:#04X} isn't supported", code)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CartridgeError {}
+
+pub struct Cartridge {
+    pub header: Header,
+    pub mapper: Box<dyn Mapper>,
+}
+
+impl Cartridge {
+    /// Parses the header of `rom` and checks the things the boot ROM checks:
+    /// the logo and the header checksum. The global checksum isn't checked
+    /// since real hardware ignores it; use `Header::validate` for that.
+    pub fn from_rom(rom: Vec<u8>) -> Result<Cartridge, CartridgeError> {
+        Cartridge::from_rom_with_clock(rom, Box::new(SystemClock))
+    }
+
+    /// Like `from_rom`, but cartridges with a real-time clock take their time
+    /// from `clock`.
+    pub fn from_rom_with_clock(
+        rom: Vec<u8>,
+        clock: Box<dyn Clock>,
+    ) -> Result<Cartridge, CartridgeError> {
+        let header = Header::parse(&rom)?;
+        header.check_logo(&rom)?;
+        header.check_header_checksum(&rom)?;
+        if rom.len() != header.rom_size {
+            return Err(CartridgeError::RomSizeMismatch {
+                header: header.rom_size,
+                actual: rom.len(),
+            });
+        }
+
+        let ram_size = header.ram_size;
+        let mapper: Box<dyn Mapper> = match header.mapper {
+            MapperKind::None => Box::new(RomOnly::new(rom, ram_size)),
+            MapperKind::Mbc1 => Box::new(Mbc1::new(rom, ram_size)),
+            MapperKind::Mbc2 => Box::new(Mbc2::new(rom)),
+            MapperKind::Mbc3 => {
+                let clock = if header.has_timer { Some(clock) } else { None };
+                Box::new(Mbc3::new(rom, ram_size, clock))
+            }
+            MapperKind::Mbc5 => Box::new(Mbc5::new(rom, ram_size, header.has_rumble)),
+            MapperKind::Mbc6 => Box::new(Mbc6::new(rom, ram_size)),
+            MapperKind::Mbc7 => Box::new(Mbc7::new(rom)),
+            MapperKind::Mmm01 => Box::new(Mmm01::new(rom, ram_size)),
+            MapperKind::HuC1 => Box::new(HuC1::new(rom, ram_size)),
+            MapperKind::HuC3 => Box::new(HuC3::new(rom, ram_size, clock)),
+            MapperKind::PocketCamera => Box::new(PocketCamera::new(rom, ram_size)),
+            // TAMA5 isn't documented well enough to emulate
+            _ => {
+                return Err(CartridgeError::UnsupportedCartridgeType(
+                    header.cartridge_type,
+                ))
+            }
+        };
+        Ok(Cartridge { header, mapper })
+    }
+
+    /// Keeps the cartridge's battery-backed memory in the `.sav` file at
+    /// `path`, loading it now if it exists. Does nothing for cartridges
+    /// without a battery.
+    pub fn attach_save_file(&mut self, path: impl Into<PathBuf>) -> io::Result<()> {
+        if !self.header.has_battery {
+            return Ok(());
+        }
+        // read it before wrapping, so a save that can't be read is never overwritten
+        let path = path.into();
+        if let Some(data) = save_file::read_save(&path)? {
+            self.mapper.load_save_data(&data);
+        }
+        let mapper = std::mem::replace(&mut self.mapper, Box::new(RomOnly::new(Vec::new(), 0)));
+        self.mapper = Box::new(SaveFile::new(mapper, path));
+        Ok(())
+    }
+
+    /// Writes battery-backed memory to the attached save file, if any.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.mapper.flush()
+    }
+
+    /// Lets a frontend know when the rumble motor turns on or off.
+    pub fn on_rumble(&mut self, handler: impl FnMut(bool) + 'static) {
+        self.mapper.set_rumble_handler(Box::new(handler));
+    }
+
+    pub fn connect_infrared(&mut self, port: impl InfraredPort + 'static) {
+        self.mapper.set_infrared_port(Box::new(port));
+    }
+
+    pub fn connect_accelerometer(&mut self, accelerometer: impl Accelerometer + 'static) {
+        self.mapper.set_accelerometer(Box::new(accelerometer));
+    }
+
+    pub fn connect_image_sensor(&mut self, sensor: impl ImageSensor + 'static) {
+        self.mapper.set_image_sensor(Box::new(sensor));
+    }
+}
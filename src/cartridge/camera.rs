@@ -0,0 +1,161 @@
+use crate::cartridge::{banked_byte, Mapper, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+pub const SENSOR_WIDTH: usize = 128;
+pub const SENSOR_HEIGHT: usize = 112;
+
+/// Where the camera's pictures come from: `SENSOR_WIDTH` x `SENSOR_HEIGHT`
+/// brightness values (0 is black), row by row.
+pub trait ImageSensor {
+    fn capture(&mut self) -> Vec<u8>;
+}
+
+impl<F: FnMut() -> Vec<u8>> ImageSensor for F {
+    fn capture(&mut self) -> Vec<u8> {
+        self()
+    }
+}
+
+// Setting bit 4 of the RAM bank register maps the camera registers in.
+const REGISTER_SELECT: u8 = 0x10;
+const REGISTER_COUNT: usize = 0x36;
+const CONTROL: usize = 0x00;
+const DITHER_MATRIX: usize = 0x06;
+const CAPTURE: u8 = 0x01;
+// Captured pictures land as tiles in RAM bank 0, just past the first 256 bytes.
+const IMAGE_ADDRESS: usize = 0x0100;
+
+/// The Game Boy Camera's controller: an MBC with 128 KiB of RAM and the
+/// registers of the M64282FP sensor. Captures finish instantly; exposure and
+/// edge enhancement settings are ignored, only the dither matrix is applied.
+pub struct PocketCamera {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    ram_enabled: bool,
+    rom_bank: u8, // 6 bits
+    ram_bank: u8, // 4 bits, plus REGISTER_SELECT
+    registers: [u8; REGISTER_COUNT],
+    sensor: Option<Box<dyn ImageSensor>>,
+}
+
+impl PocketCamera {
+    pub fn new(rom: Vec<u8>, ram_size: usize) -> PocketCamera {
+        PocketCamera {
+            rom,
+            ram: vec![0; ram_size],
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            registers: [0; REGISTER_COUNT],
+            sensor: None,
+        }
+    }
+
+    fn ram_offset(&self, address: u16) -> Option<usize> {
+        if self.ram.is_empty() {
+            return None;
+        }
+        let offset = (self.ram_bank & 0x0F) as usize * RAM_BANK_SIZE + address as usize;
+        Some(offset % self.ram.len())
+    }
+
+    fn capture(&mut self) {
+        let image = match self.sensor.as_mut() {
+            Some(sensor) => sensor.capture(),
+            None => vec![0xFF; SENSOR_WIDTH * SENSOR_HEIGHT],
+        };
+        if self.ram.len() < IMAGE_ADDRESS + SENSOR_WIDTH * SENSOR_HEIGHT / 4 {
+            return;
+        }
+        for y in 0..SENSOR_HEIGHT {
+            for x in 0..SENSOR_WIDTH {
+                let brightness = image.get(y * SENSOR_WIDTH + x).copied().unwrap_or(0xFF);
+                // each cell of the 4x4 matrix holds three thresholds, darkest first
+                let cell = DITHER_MATRIX + ((y % 4) * 4 + x % 4) * 3;
+                let thresholds = &self.registers[cell..cell + 3];
+                let colour = thresholds
+                    .iter()
+                    .filter(|threshold| brightness < **threshold)
+                    .count() as u8;
+
+                let tile = (y / 8) * (SENSOR_WIDTH / 8) + x / 8;
+                let row = IMAGE_ADDRESS + tile * 16 + (y % 8) * 2;
+                let bit = 0x80 >> (x % 8);
+                if colour & 0x01 != 0 {
+                    self.ram[row] |= bit;
+                } else {
+                    self.ram[row] &= !bit;
+                }
+                if colour & 0x02 != 0 {
+                    self.ram[row + 1] |= bit;
+                } else {
+                    self.ram[row + 1] &= !bit;
+                }
+            }
+        }
+    }
+}
+
+impl Mapper for PocketCamera {
+    fn read_rom(&mut self, address: u16) -> u8 {
+        match address {
+            0x0000..=0x3FFF => banked_byte(&self.rom, ROM_BANK_SIZE, 0, address),
+            _ => banked_byte(&self.rom, ROM_BANK_SIZE, self.rom_bank as usize, address),
+        }
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = value & 0x3F,
+            0x4000..=0x5FFF => self.ram_bank = value & (REGISTER_SELECT | 0x0F),
+            _ => {}
+        }
+    }
+
+    // RAM can be read even while writes to it are disabled.
+    fn read_ram(&mut self, address: u16) -> u8 {
+        if self.ram_bank & REGISTER_SELECT != 0 {
+            // only the control register reads back, and it's never busy
+            return match address as usize & 0x7F {
+                CONTROL => self.registers[CONTROL] & !CAPTURE,
+                _ => 0x00,
+            };
+        }
+        match self.ram_offset(address) {
+            Some(offset) => self.ram[offset],
+            None => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        if self.ram_bank & REGISTER_SELECT != 0 {
+            let register = address as usize & 0x7F;
+            if register < REGISTER_COUNT {
+                self.registers[register] = value;
+            }
+            if register == CONTROL && value & CAPTURE != 0 {
+                self.capture();
+            }
+            return;
+        }
+        if !self.ram_enabled {
+            return;
+        }
+        if let Some(offset) = self.ram_offset(address) {
+            self.ram[offset] = value;
+        }
+    }
+
+    fn save_data(&mut self) -> Vec<u8> {
+        self.ram.clone()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        let size = self.ram.len().min(data.len());
+        self.ram[..size].copy_from_slice(&data[..size]);
+    }
+
+    fn set_image_sensor(&mut self, sensor: Box<dyn ImageSensor>) {
+        self.sensor = Some(sensor);
+    }
+}
@@ -0,0 +1,231 @@
+use crate::cartridge::CartridgeError;
+
+pub const HEADER_END: usize = 0x0150;
+
+pub const NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+
+const LOGO: usize = 0x0104;
+const TITLE: usize = 0x0134;
+const CGB_FLAG: usize = 0x0143;
+const NEW_LICENSEE: usize = 0x0144;
+const SGB_FLAG: usize = 0x0146;
+const CARTRIDGE_TYPE: usize = 0x0147;
+const ROM_SIZE: usize = 0x0148;
+const RAM_SIZE: usize = 0x0149;
+const DESTINATION: usize = 0x014A;
+const OLD_LICENSEE: usize = 0x014B;
+const VERSION: usize = 0x014C;
+const HEADER_CHECKSUM: usize = 0x014D;
+const GLOBAL_CHECKSUM: usize = 0x014E;
+
+// An old licensee code of 0x33 means the new, two character one is used.
+const USE_NEW_LICENSEE: u8 = 0x33;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CgbSupport {
+    None,
+    Compatible, // 0x80, works on DMG too
+    Only,       // 0xC0
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Licensee {
+    Old(u8),
+    New(String),
+}
+
+/// The memory bank controller (or lack of one) a cartridge uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapperKind {
+    None,
+    Mbc1,
+    Mbc2,
+    Mmm01,
+    Mbc3,
+    Mbc5,
+    Mbc6,
+    Mbc7,
+    PocketCamera,
+    Tama5,
+    HuC3,
+    HuC1,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub title: String,
+    pub cgb: CgbSupport,
+    pub sgb: bool,
+    pub licensee: Licensee,
+    pub cartridge_type: u8,
+    pub mapper: MapperKind,
+    pub has_ram: bool,
+    pub has_battery: bool,
+    pub has_timer: bool,
+    pub has_rumble: bool,
+    pub rom_size: usize, // in bytes
+    pub ram_size: usize, // in bytes
+    pub japanese: bool,
+    pub version: u8,
+    pub header_checksum: u8,
+    pub global_checksum: u16,
+}
+
+impl Header {
+    /// Reads the header at 0x0100-0x014F. This only fails if the header can't
+    /// be made sense of; see `validate` for the checksums and logo.
+    pub fn parse(rom: &[u8]) -> Result<Header, CartridgeError> {
+        if rom.len() < HEADER_END {
+            return Err(CartridgeError::TooSmall(rom.len()));
+        }
+
+        let cgb = match rom[CGB_FLAG] {
+            0xC0 => CgbSupport::Only,
+            0x80 => CgbSupport::Compatible,
+            _ => CgbSupport::None,
+        };
+        // CGB games use the end of the title area for the CGB flag and a
+        // manufacturer code
+        let title_end = if cgb == CgbSupport::None {
+            CGB_FLAG + 1
+        } else {
+            CGB_FLAG - 4
+        };
+        let title = rom[TITLE..title_end]
+            .iter()
+            .take_while(|byte| **byte != 0)
+            .map(|byte| *byte as char)
+            .collect();
+
+        let licensee = match rom[OLD_LICENSEE] {
+            USE_NEW_LICENSEE => Licensee::New(
+                rom[NEW_LICENSEE..NEW_LICENSEE + 2]
+                    .iter()
+                    .map(|byte| *byte as char)
+                    .collect(),
+            ),
+            code => Licensee::Old(code),
+        };
+
+        let cartridge_type = rom[CARTRIDGE_TYPE];
+        let (mapper, has_ram, has_battery, has_timer, has_rumble) = match cartridge_type {
+            0x00 => (MapperKind::None, false, false, false, false),
+            0x01 => (MapperKind::Mbc1, false, false, false, false),
+            0x02 => (MapperKind::Mbc1, true, false, false, false),
+            0x03 => (MapperKind::Mbc1, true, true, false, false),
+            0x05 => (MapperKind::Mbc2, true, false, false, false),
+            0x06 => (MapperKind::Mbc2, true, true, false, false),
+            0x08 => (MapperKind::None, true, false, false, false),
+            0x09 => (MapperKind::None, true, true, false, false),
+            0x0B => (MapperKind::Mmm01, false, false, false, false),
+            0x0C => (MapperKind::Mmm01, true, false, false, false),
+            0x0D => (MapperKind::Mmm01, true, true, false, false),
+            0x0F => (MapperKind::Mbc3, false, true, true, false),
+            0x10 => (MapperKind::Mbc3, true, true, true, false),
+            0x11 => (MapperKind::Mbc3, false, false, false, false),
+            0x12 => (MapperKind::Mbc3, true, false, false, false),
+            0x13 => (MapperKind::Mbc3, true, true, false, false),
+            0x19 => (MapperKind::Mbc5, false, false, false, false),
+            0x1A => (MapperKind::Mbc5, true, false, false, false),
+            0x1B => (MapperKind::Mbc5, true, true, false, false),
+            0x1C => (MapperKind::Mbc5, false, false, false, true),
+            0x1D => (MapperKind::Mbc5, true, false, false, true),
+            0x1E => (MapperKind::Mbc5, true, true, false, true),
+            0x20 => (MapperKind::Mbc6, true, true, false, false),
+            0x22 => (MapperKind::Mbc7, true, true, false, false),
+            0xFC => (MapperKind::PocketCamera, true, true, false, false),
+            0xFD => (MapperKind::Tama5, true, true, true, false),
+            0xFE => (MapperKind::HuC3, true, true, true, false),
+            0xFF => (MapperKind::HuC1, true, true, false, false),
+            code => return Err(CartridgeError::UnknownCartridgeType(code)),
+        };
+
+        let rom_size = match rom[ROM_SIZE] {
+            code @ 0x00..=0x08 => 0x8000 << code,
+            code => return Err(CartridgeError::UnknownRomSize(code)),
+        };
+        let ram_size = match rom[RAM_SIZE] {
+            0x00 => 0,
+            0x01 => 0x800,
+            0x02 => 0x2000,
+            0x03 => 0x8000,
+            0x04 => 0x20000,
+            0x05 => 0x10000,
+            code => return Err(CartridgeError::UnknownRamSize(code)),
+        };
+
+        Ok(Header {
+            title,
+            cgb,
+            sgb: rom[SGB_FLAG] == 0x03,
+            licensee,
+            cartridge_type,
+            mapper,
+            has_ram,
+            has_battery,
+            has_timer,
+            has_rumble,
+            rom_size,
+            ram_size,
+            japanese: rom[DESTINATION] == 0x00,
+            version: rom[VERSION],
+            header_checksum: rom[HEADER_CHECKSUM],
+            global_checksum: u16::from_be_bytes([rom[GLOBAL_CHECKSUM], rom[GLOBAL_CHECKSUM + 1]]),
+        })
+    }
+
+    /// Checks the logo, header checksum and global checksum of `rom`.
+    pub fn validate(&self, rom: &[u8]) -> Result<(), CartridgeError> {
+        self.check_logo(rom)?;
+        self.check_header_checksum(rom)?;
+        self.check_global_checksum(rom)
+    }
+
+    pub fn check_logo(&self, rom: &[u8]) -> Result<(), CartridgeError> {
+        if rom[LOGO..LOGO + NINTENDO_LOGO.len()] != NINTENDO_LOGO {
+            return Err(CartridgeError::BadLogo);
+        }
+        Ok(())
+    }
+
+    pub fn check_header_checksum(&self, rom: &[u8]) -> Result<(), CartridgeError> {
+        let actual = header_checksum(rom);
+        if actual != self.header_checksum {
+            return Err(CartridgeError::HeaderChecksum {
+                expected: self.header_checksum,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn check_global_checksum(&self, rom: &[u8]) -> Result<(), CartridgeError> {
+        let actual = global_checksum(rom);
+        if actual != self.global_checksum {
+            return Err(CartridgeError::GlobalChecksum {
+                expected: self.global_checksum,
+                actual,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Checksum over 0x0134-0x014C, as computed by the boot ROM.
+pub fn header_checksum(rom: &[u8]) -> u8 {
+    rom[TITLE..=VERSION]
+        .iter()
+        .fold(0u8, |sum, byte| sum.wrapping_sub(*byte).wrapping_sub(1))
+}
+
+/// Sum of every byte in the ROM except the two global checksum bytes.
+pub fn global_checksum(rom: &[u8]) -> u16 {
+    rom.iter()
+        .enumerate()
+        .filter(|(address, _)| *address != GLOBAL_CHECKSUM && *address != GLOBAL_CHECKSUM + 1)
+        .fold(0u16, |sum, (_, byte)| sum.wrapping_add(*byte as u16))
+}
@@ -0,0 +1,93 @@
+use crate::cartridge::{banked_byte, InfraredPort, Mapper, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+// Writing this to 0000-1FFF puts the infrared port at A000-BFFF instead of RAM.
+const INFRARED_MODE: u8 = 0x0E;
+
+/// Hudson's HuC1, an MBC1 lookalike with an infrared LED and sensor. There's
+/// no RAM enable; the register at 0000-1FFF switches between RAM and IR.
+pub struct HuC1 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    infrared_mode: bool,
+    rom_bank: u8, // 6 bits
+    ram_bank: u8, // 2 bits
+    infrared: Option<Box<dyn InfraredPort>>,
+}
+
+impl HuC1 {
+    pub fn new(rom: Vec<u8>, ram_size: usize) -> HuC1 {
+        HuC1 {
+            rom,
+            ram: vec![0; ram_size],
+            infrared_mode: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            infrared: None,
+        }
+    }
+
+    fn ram_offset(&self, address: u16) -> Option<usize> {
+        if self.ram.is_empty() {
+            return None;
+        }
+        let offset = self.ram_bank as usize * RAM_BANK_SIZE + address as usize;
+        Some(offset % self.ram.len())
+    }
+}
+
+impl Mapper for HuC1 {
+    fn read_rom(&mut self, address: u16) -> u8 {
+        match address {
+            0x0000..=0x3FFF => banked_byte(&self.rom, ROM_BANK_SIZE, 0, address),
+            _ => banked_byte(&self.rom, ROM_BANK_SIZE, self.rom_bank as usize, address),
+        }
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.infrared_mode = value & 0x0F == INFRARED_MODE,
+            0x2000..=0x3FFF => self.rom_bank = (value & 0x3F).max(1),
+            0x4000..=0x5FFF => self.ram_bank = value & 0x03,
+            _ => {}
+        }
+    }
+
+    fn read_ram(&mut self, address: u16) -> u8 {
+        if self.infrared_mode {
+            let light = match self.infrared.as_mut() {
+                Some(port) => port.light_detected(),
+                None => false,
+            };
+            return 0xC0 | light as u8;
+        }
+        match self.ram_offset(address) {
+            Some(offset) => self.ram[offset],
+            None => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        if self.infrared_mode {
+            if let Some(port) = self.infrared.as_mut() {
+                port.set_led(value & 0x01 != 0);
+            }
+            return;
+        }
+        if let Some(offset) = self.ram_offset(address) {
+            self.ram[offset] = value;
+        }
+    }
+
+    fn save_data(&mut self) -> Vec<u8> {
+        self.ram.clone()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        let size = self.ram.len().min(data.len());
+        self.ram[..size].copy_from_slice(&data[..size]);
+    }
+
+    fn set_infrared_port(&mut self, port: Box<dyn InfraredPort>) {
+        self.infrared = Some(port);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::cartridge::rtc::Clock;
+use crate::cartridge::{banked_byte, InfraredPort, Mapper, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+// What A000-BFFF is connected to, set by writing to 0000-1FFF.
+const MODE_RAM_READ: u8 = 0x00;
+const MODE_RAM: u8 = 0x0A;
+const MODE_RTC_COMMAND: u8 = 0x0B;
+const MODE_RTC_RESPONSE: u8 = 0x0C;
+const MODE_RTC_SEMAPHORE: u8 = 0x0D;
+const MODE_INFRARED: u8 = 0x0E;
+
+const RTC_READ: u8 = 0x1;
+const RTC_WRITE: u8 = 0x3;
+const RTC_ADDRESS_LOW: u8 = 0x4;
+const RTC_ADDRESS_HIGH: u8 = 0x5;
+const RTC_EXTENDED: u8 = 0x6;
+
+const MINUTES_PER_DAY: u64 = 1440;
+
+// Size of the clock state appended to save files: the counter in seconds and
+// the time it was saved at, both 64-bit little endian.
+pub const HUC3_RTC_SAVE_SIZE: usize = 16;
+
+/// Hudson's HuC3, with an infrared port and a clock that counts minutes and
+/// days. The clock sits behind a small nibble-wide memory the game talks to
+/// with one byte commands; commands complete as soon as they're written.
+pub struct HuC3 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    mode: u8,
+    rom_bank: u8, // 7 bits
+    ram_bank: u8, // 2 bits
+    clock: Box<dyn Clock>,
+    offset: i64, // counter = clock time + offset, in seconds
+    memory: [u8; 256],
+    address: u8,
+    last_command: u8,
+    response: u8,
+    infrared: Option<Box<dyn InfraredPort>>,
+}
+
+impl HuC3 {
+    pub fn new(rom: Vec<u8>, ram_size: usize, clock: Box<dyn Clock>) -> HuC3 {
+        let offset = -(clock.now() as i64);
+        HuC3 {
+            rom,
+            ram: vec![0; ram_size],
+            mode: MODE_RAM_READ,
+            rom_bank: 1,
+            ram_bank: 0,
+            clock,
+            offset,
+            memory: [0; 256],
+            address: 0,
+            last_command: 0,
+            response: 0,
+            infrared: None,
+        }
+    }
+
+    fn counter(&self) -> u64 {
+        (self.clock.now() as i64).wrapping_add(self.offset) as u64
+    }
+
+    fn set_counter(&mut self, seconds: u64) {
+        self.offset = (seconds as i64).wrapping_sub(self.clock.now() as i64);
+    }
+
+    fn ram_offset(&self, address: u16) -> Option<usize> {
+        if self.ram.is_empty() {
+            return None;
+        }
+        let offset = self.ram_bank as usize * RAM_BANK_SIZE + address as usize;
+        Some(offset % self.ram.len())
+    }
+
+    fn rtc_command(&mut self, value: u8) {
+        let command = (value >> 4) & 0x07;
+        let argument = value & 0x0F;
+        self.last_command = command;
+        match command {
+            RTC_READ => {
+                self.response = self.memory[self.address as usize];
+                self.address = self.address.wrapping_add(1);
+            }
+            RTC_WRITE => {
+                self.memory[self.address as usize] = argument;
+                self.address = self.address.wrapping_add(1);
+            }
+            RTC_ADDRESS_LOW => self.address = self.address & 0xF0 | argument,
+            RTC_ADDRESS_HIGH => self.address = self.address & 0x0F | argument << 4,
+            RTC_EXTENDED => self.rtc_extended(argument),
+            _ => {}
+        }
+    }
+
+    // The time lives in memory 0x00-0x05 as 12 bits of minutes then 12 bits
+    // of days, least significant nibble first.
+    fn rtc_extended(&mut self, argument: u8) {
+        match argument {
+            0x0 => {
+                let minutes = self.counter() / 60;
+                let days = (minutes / MINUTES_PER_DAY) & 0xFFF;
+                let time = (minutes % MINUTES_PER_DAY) | (days << 12);
+                for i in 0..6 {
+                    self.memory[i] = (time >> (i * 4)) as u8 & 0x0F;
+                }
+            }
+            0x1 => {
+                let time = (0..6).fold(0u64, |time, i| time | (self.memory[i] as u64) << (i * 4));
+                let minutes = (time & 0xFFF) + (time >> 12) * MINUTES_PER_DAY;
+                self.set_counter(minutes * 60);
+            }
+            0x2 => self.response = 0x1,
+            _ => {}
+        }
+    }
+}
+
+impl Mapper for HuC3 {
+    fn read_rom(&mut self, address: u16) -> u8 {
+        match address {
+            0x0000..=0x3FFF => banked_byte(&self.rom, ROM_BANK_SIZE, 0, address),
+            _ => banked_byte(&self.rom, ROM_BANK_SIZE, self.rom_bank as usize, address),
+        }
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.mode = value & 0x0F,
+            0x2000..=0x3FFF => self.rom_bank = value & 0x7F,
+            0x4000..=0x5FFF => self.ram_bank = value & 0x03,
+            _ => {}
+        }
+    }
+
+    fn read_ram(&mut self, address: u16) -> u8 {
+        match self.mode {
+            MODE_RAM_READ | MODE_RAM => match self.ram_offset(address) {
+                Some(offset) => self.ram[offset],
+                None => 0xFF,
+            },
+            MODE_RTC_RESPONSE => self.last_command << 4 | self.response,
+            // always ready, as commands finish immediately
+            MODE_RTC_SEMAPHORE => 0x01,
+            MODE_INFRARED => {
+                let light = match self.infrared.as_mut() {
+                    Some(port) => port.light_detected(),
+                    None => false,
+                };
+                0xC0 | light as u8
+            }
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        match self.mode {
+            MODE_RAM => {
+                if let Some(offset) = self.ram_offset(address) {
+                    self.ram[offset] = value;
+                }
+            }
+            MODE_RTC_COMMAND => self.rtc_command(value),
+            MODE_INFRARED => {
+                if let Some(port) = self.infrared.as_mut() {
+                    port.set_led(value & 0x01 != 0);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn save_data(&mut self) -> Vec<u8> {
+        let mut data = self.ram.clone();
+        data.extend_from_slice(&self.counter().to_le_bytes());
+        data.extend_from_slice(&self.clock.now().to_le_bytes());
+        data
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        let ram_size = self.ram.len().min(data.len());
+        self.ram[..ram_size].copy_from_slice(&data[..ram_size]);
+        let rtc = &data[ram_size..];
+        if rtc.len() >= HUC3_RTC_SAVE_SIZE {
+            let counter = u64::from_le_bytes(rtc[0..8].try_into().unwrap());
+            let saved_at = u64::from_le_bytes(rtc[8..16].try_into().unwrap());
+            let elapsed = self.clock.now().saturating_sub(saved_at);
+            self.set_counter(counter.wrapping_add(elapsed));
+        }
+    }
+
+    fn set_infrared_port(&mut self, port: Box<dyn InfraredPort>) {
+        self.infrared = Some(port);
+    }
+}
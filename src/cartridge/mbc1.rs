@@ -0,0 +1,130 @@
+use crate::cartridge::header::NINTENDO_LOGO;
+use crate::cartridge::{banked_byte, Mapper, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+pub struct Mbc1 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    ram_enabled: bool,
+    bank1: u8, // 5 bits, 4 on multicarts
+    bank2: u8, // 2 bits
+    advanced_banking: bool,
+    multicart: bool,
+}
+
+impl Mbc1 {
+    pub fn new(rom: Vec<u8>, ram_size: usize) -> Mbc1 {
+        let multicart = is_multicart(&rom);
+        Mbc1 {
+            rom,
+            ram: vec![0; ram_size],
+            ram_enabled: false,
+            bank1: 1,
+            bank2: 0,
+            advanced_banking: false,
+            multicart,
+        }
+    }
+
+    pub fn is_multicart(&self) -> bool {
+        self.multicart
+    }
+
+    // bank2 sits above the 5 bits of bank1, or above 4 bits on MBC1M
+    fn bank2_shift(&self) -> u8 {
+        if self.multicart {
+            4
+        } else {
+            5
+        }
+    }
+
+    fn rom_bank_0(&self) -> usize {
+        if self.advanced_banking {
+            (self.bank2 as usize) << self.bank2_shift()
+        } else {
+            0
+        }
+    }
+
+    fn rom_bank_n(&self) -> usize {
+        let bank1 = if self.multicart {
+            self.bank1 & 0x0F
+        } else {
+            self.bank1
+        };
+        (self.bank2 as usize) << self.bank2_shift() | bank1 as usize
+    }
+
+    fn ram_bank(&self) -> usize {
+        if self.advanced_banking {
+            self.bank2 as usize
+        } else {
+            0
+        }
+    }
+
+    fn rom_byte(&self, bank: usize, address: u16) -> u8 {
+        banked_byte(&self.rom, ROM_BANK_SIZE, bank, address)
+    }
+
+    fn ram_offset(&self, address: u16) -> Option<usize> {
+        if !self.ram_enabled || self.ram.is_empty() {
+            return None;
+        }
+        let offset = self.ram_bank() * RAM_BANK_SIZE + address as usize;
+        Some(offset % self.ram.len())
+    }
+}
+
+impl Mapper for Mbc1 {
+    fn read_rom(&mut self, address: u16) -> u8 {
+        match address {
+            0x0000..=0x3FFF => self.rom_byte(self.rom_bank_0(), address),
+            _ => self.rom_byte(self.rom_bank_n(), address),
+        }
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+            // only the 5 bit register is checked for 0, which is why banks
+            // 0x20, 0x40 and 0x60 can't be mapped at 0x4000
+            0x2000..=0x3FFF => self.bank1 = (value & 0x1F).max(1),
+            0x4000..=0x5FFF => self.bank2 = value & 0x03,
+            _ => self.advanced_banking = value & 0x01 != 0,
+        }
+    }
+
+    fn read_ram(&mut self, address: u16) -> u8 {
+        match self.ram_offset(address) {
+            Some(offset) => self.ram[offset],
+            None => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        if let Some(offset) = self.ram_offset(address) {
+            self.ram[offset] = value;
+        }
+    }
+
+    fn save_data(&mut self) -> Vec<u8> {
+        self.ram.clone()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        let size = self.ram.len().min(data.len());
+        self.ram[..size].copy_from_slice(&data[..size]);
+    }
+}
+
+// MBC1M multicarts are 1 MiB and wire bank2 to ROM address lines 18-19
+// instead of 19-20, giving four 256 KiB games. Each of those starts with a
+// header of its own, so a logo at the start of the second game gives it away.
+fn is_multicart(rom: &[u8]) -> bool {
+    const GAME_SIZE: usize = 0x10 * ROM_BANK_SIZE;
+    const LOGO: usize = 0x0104;
+
+    rom.len() == 0x40 * ROM_BANK_SIZE
+        && rom[GAME_SIZE + LOGO..GAME_SIZE + LOGO + NINTENDO_LOGO.len()] == NINTENDO_LOGO
+}
@@ -0,0 +1,73 @@
+use crate::cartridge::{banked_byte, Mapper, ROM_BANK_SIZE};
+
+// 512 half-bytes of RAM are built into the controller.
+const RAM_SIZE: usize = 0x200;
+
+pub struct Mbc2 {
+    rom: Vec<u8>,
+    ram: [u8; RAM_SIZE],
+    ram_enabled: bool,
+    rom_bank: u8, // 4 bits
+}
+
+impl Mbc2 {
+    pub fn new(rom: Vec<u8>) -> Mbc2 {
+        Mbc2 {
+            rom,
+            ram: [0; RAM_SIZE],
+            ram_enabled: false,
+            rom_bank: 1,
+        }
+    }
+
+    fn rom_byte(&self, bank: usize, address: u16) -> u8 {
+        banked_byte(&self.rom, ROM_BANK_SIZE, bank, address)
+    }
+}
+
+impl Mapper for Mbc2 {
+    fn read_rom(&mut self, address: u16) -> u8 {
+        match address {
+            0x0000..=0x3FFF => self.rom_byte(0, address),
+            _ => self.rom_byte(self.rom_bank as usize, address),
+        }
+    }
+
+    // There's a single register range, 0000-3FFF. Address bit 8 picks between
+    // RAM enable (clear) and the ROM bank number (set).
+    fn write_rom(&mut self, address: u16, value: u8) {
+        if address >= 0x4000 {
+            return;
+        }
+        if address & 0x0100 == 0 {
+            self.ram_enabled = value & 0x0F == 0x0A;
+        } else {
+            self.rom_bank = (value & 0x0F).max(1);
+        }
+    }
+
+    // Only the low 9 address bits are decoded, so the RAM repeats through
+    // A000-BFFF. The upper nibble isn't connected and reads as 1s.
+    fn read_ram(&mut self, address: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+        0xF0 | self.ram[address as usize % RAM_SIZE]
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        if self.ram_enabled {
+            self.ram[address as usize % RAM_SIZE] = value & 0x0F;
+        }
+    }
+
+    fn save_data(&mut self) -> Vec<u8> {
+        self.ram.to_vec()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        for (byte, saved) in self.ram.iter_mut().zip(data) {
+            *byte = saved & 0x0F;
+        }
+    }
+}
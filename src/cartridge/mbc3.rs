@@ -0,0 +1,112 @@
+use crate::cartridge::rtc::{Clock, Rtc, RTC_DAY_HIGH, RTC_SECONDS};
+use crate::cartridge::{banked_byte, Mapper, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+pub struct Mbc3 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    rtc: Option<Rtc>,
+    ram_enabled: bool,
+    rom_bank: u8,
+    ram_select: u8, // RAM bank 0-3, or RTC register 0x08-0x0C
+}
+
+impl Mbc3 {
+    /// `clock` is only used if the cartridge has a timer.
+    pub fn new(rom: Vec<u8>, ram_size: usize, clock: Option<Box<dyn Clock>>) -> Mbc3 {
+        Mbc3 {
+            rom,
+            ram: vec![0; ram_size],
+            rtc: clock.map(Rtc::new),
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_select: 0,
+        }
+    }
+
+    fn rom_byte(&self, bank: usize, address: u16) -> u8 {
+        banked_byte(&self.rom, ROM_BANK_SIZE, bank, address)
+    }
+
+    fn ram_offset(&self, address: u16) -> Option<usize> {
+        if self.ram.is_empty() {
+            return None;
+        }
+        let offset = self.ram_select as usize * RAM_BANK_SIZE + address as usize;
+        Some(offset % self.ram.len())
+    }
+
+    fn rtc_selected(&self) -> bool {
+        (RTC_SECONDS..=RTC_DAY_HIGH).contains(&self.ram_select)
+    }
+}
+
+impl Mapper for Mbc3 {
+    fn read_rom(&mut self, address: u16) -> u8 {
+        match address {
+            0x0000..=0x3FFF => self.rom_byte(0, address),
+            _ => self.rom_byte(self.rom_bank as usize, address),
+        }
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = (value & 0x7F).max(1),
+            0x4000..=0x5FFF => self.ram_select = value & 0x0F,
+            _ => {
+                if let Some(rtc) = self.rtc.as_mut() {
+                    rtc.write_latch(value);
+                }
+            }
+        }
+    }
+
+    fn read_ram(&mut self, address: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+        if self.rtc_selected() {
+            return match &self.rtc {
+                Some(rtc) => rtc.read(self.ram_select),
+                None => 0xFF,
+            };
+        }
+        match self.ram_offset(address) {
+            Some(offset) if self.ram_select < 0x08 => self.ram[offset],
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        if !self.ram_enabled {
+            return;
+        }
+        if self.rtc_selected() {
+            if let Some(rtc) = self.rtc.as_mut() {
+                rtc.write(self.ram_select, value);
+            }
+            return;
+        }
+        if self.ram_select < 0x08 {
+            if let Some(offset) = self.ram_offset(address) {
+                self.ram[offset] = value;
+            }
+        }
+    }
+
+    fn save_data(&mut self) -> Vec<u8> {
+        let mut data = self.ram.clone();
+        if let Some(rtc) = self.rtc.as_mut() {
+            data.extend_from_slice(&rtc.save());
+        }
+        data
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        let ram_size = self.ram.len().min(data.len());
+        self.ram[..ram_size].copy_from_slice(&data[..ram_size]);
+        if let Some(rtc) = self.rtc.as_mut() {
+            rtc.load(&data[ram_size..]);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::cartridge::{banked_byte, Mapper, RumbleHandler, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+// On rumble cartridges bit 3 of the RAM bank register drives the motor.
+const RUMBLE_BIT: u8 = 0x08;
+
+pub struct Mbc5 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    ram_enabled: bool,
+    rom_bank: u16, // 9 bits
+    ram_bank: u8,  // 4 bits, 3 on rumble cartridges
+    has_rumble: bool,
+    rumbling: bool,
+    rumble_handler: Option<RumbleHandler>,
+}
+
+impl Mbc5 {
+    pub fn new(rom: Vec<u8>, ram_size: usize, has_rumble: bool) -> Mbc5 {
+        Mbc5 {
+            rom,
+            ram: vec![0; ram_size],
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            has_rumble,
+            rumbling: false,
+            rumble_handler: None,
+        }
+    }
+
+    pub fn is_rumbling(&self) -> bool {
+        self.rumbling
+    }
+
+    fn set_rumble(&mut self, on: bool) {
+        if on == self.rumbling {
+            return;
+        }
+        self.rumbling = on;
+        if let Some(handler) = self.rumble_handler.as_mut() {
+            handler(on);
+        }
+    }
+
+    fn rom_byte(&self, bank: usize, address: u16) -> u8 {
+        banked_byte(&self.rom, ROM_BANK_SIZE, bank, address)
+    }
+
+    fn ram_offset(&self, address: u16) -> Option<usize> {
+        if !self.ram_enabled || self.ram.is_empty() {
+            return None;
+        }
+        let offset = self.ram_bank as usize * RAM_BANK_SIZE + address as usize;
+        Some(offset % self.ram.len())
+    }
+}
+
+impl Mapper for Mbc5 {
+    fn read_rom(&mut self, address: u16) -> u8 {
+        match address {
+            0x0000..=0x3FFF => self.rom_byte(0, address),
+            _ => self.rom_byte(self.rom_bank as usize, address),
+        }
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+            // unlike the older controllers, bank 0 can be mapped at 4000-7FFF
+            0x2000..=0x2FFF => self.rom_bank = self.rom_bank & 0x100 | value as u16,
+            0x3000..=0x3FFF => self.rom_bank = self.rom_bank & 0xFF | ((value & 1) as u16) << 8,
+            0x4000..=0x5FFF => {
+                if self.has_rumble {
+                    self.ram_bank = value & 0x07;
+                    self.set_rumble(value & RUMBLE_BIT != 0);
+                } else {
+                    self.ram_bank = value & 0x0F;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn read_ram(&mut self, address: u16) -> u8 {
+        match self.ram_offset(address) {
+            Some(offset) => self.ram[offset],
+            None => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        if let Some(offset) = self.ram_offset(address) {
+            self.ram[offset] = value;
+        }
+    }
+
+    fn save_data(&mut self) -> Vec<u8> {
+        self.ram.clone()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        let size = self.ram.len().min(data.len());
+        self.ram[..size].copy_from_slice(&data[..size]);
+    }
+
+    fn set_rumble_handler(&mut self, handler: RumbleHandler) {
+        self.rumble_handler = Some(handler);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::cartridge::{banked_byte, Mapper};
+
+// MBC6 switches ROM and flash in 8 KiB halves of 4000-7FFF, and RAM in 4 KiB
+// halves of A000-BFFF.
+const ROM_HALF_BANK_SIZE: usize = 0x2000;
+const RAM_HALF_BANK_SIZE: usize = 0x1000;
+const FLASH_SIZE: usize = 0x100000;
+// The flash chip's sectors aren't all the same size; treating them as uniform
+// 64 KiB blocks is close enough for the one game that uses it.
+const FLASH_SECTOR_SIZE: usize = 0x10000;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FlashState {
+    Ready,
+    Unlock1,
+    Unlock2,
+    Program,
+    EraseUnlock0,
+    EraseUnlock1,
+    EraseUnlock2,
+}
+
+/// The 1 MiB flash chip. Commands are recognised by their data bytes alone;
+/// the unlock addresses aren't checked.
+struct Flash {
+    data: Vec<u8>,
+    state: FlashState,
+}
+
+impl Flash {
+    fn write(&mut self, offset: usize, value: u8) {
+        if value == 0xF0 {
+            self.state = FlashState::Ready;
+            return;
+        }
+        self.state = match (self.state, value) {
+            (FlashState::Ready, 0xAA) => FlashState::Unlock1,
+            (FlashState::Unlock1, 0x55) => FlashState::Unlock2,
+            (FlashState::Unlock2, 0xA0) => FlashState::Program,
+            (FlashState::Unlock2, 0x80) => FlashState::EraseUnlock0,
+            (FlashState::Program, _) => {
+                // programming can only clear bits
+                self.data[offset] &= value;
+                FlashState::Ready
+            }
+            (FlashState::EraseUnlock0, 0xAA) => FlashState::EraseUnlock1,
+            (FlashState::EraseUnlock1, 0x55) => FlashState::EraseUnlock2,
+            (FlashState::EraseUnlock2, 0x10) => {
+                self.data.fill(0xFF);
+                FlashState::Ready
+            }
+            (FlashState::EraseUnlock2, 0x30) => {
+                let start = offset & !(FLASH_SECTOR_SIZE - 1);
+                self.data[start..start + FLASH_SECTOR_SIZE].fill(0xFF);
+                FlashState::Ready
+            }
+            _ => FlashState::Ready,
+        };
+    }
+}
+
+pub struct Mbc6 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    flash: Flash,
+    ram_enabled: bool,
+    flash_enabled: bool,
+    flash_write_enabled: bool,
+    ram_banks: [u8; 2],
+    rom_banks: [u8; 2],
+    flash_selected: [bool; 2],
+}
+
+impl Mbc6 {
+    pub fn new(rom: Vec<u8>, ram_size: usize) -> Mbc6 {
+        Mbc6 {
+            rom,
+            ram: vec![0; ram_size],
+            flash: Flash {
+                data: vec![0xFF; FLASH_SIZE],
+                state: FlashState::Ready,
+            },
+            ram_enabled: false,
+            flash_enabled: false,
+            flash_write_enabled: false,
+            ram_banks: [0, 0],
+            rom_banks: [0, 0],
+            flash_selected: [false, false],
+        }
+    }
+
+    fn flash_offset(&self, half: usize, address: u16) -> usize {
+        let offset = self.rom_banks[half] as usize * ROM_HALF_BANK_SIZE
+            + (address as usize & (ROM_HALF_BANK_SIZE - 1));
+        offset % FLASH_SIZE
+    }
+
+    fn ram_offset(&self, address: u16) -> Option<usize> {
+        if !self.ram_enabled || self.ram.is_empty() {
+            return None;
+        }
+        let half = (address as usize) / RAM_HALF_BANK_SIZE;
+        let offset = self.ram_banks[half] as usize * RAM_HALF_BANK_SIZE
+            + (address as usize & (RAM_HALF_BANK_SIZE - 1));
+        Some(offset % self.ram.len())
+    }
+}
+
+impl Mapper for Mbc6 {
+    fn read_rom(&mut self, address: u16) -> u8 {
+        if address < 0x4000 {
+            return self.rom.get(address as usize).copied().unwrap_or(0xFF);
+        }
+        let half = (address as usize - 0x4000) / ROM_HALF_BANK_SIZE;
+        if self.flash_selected[half] {
+            if !self.flash_enabled {
+                return 0xFF;
+            }
+            return self.flash.data[self.flash_offset(half, address)];
+        }
+        banked_byte(
+            &self.rom,
+            ROM_HALF_BANK_SIZE,
+            self.rom_banks[half] as usize,
+            address,
+        )
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x03FF => self.ram_enabled = value & 0x0F == 0x0A,
+            0x0400..=0x07FF => self.ram_banks[0] = value & 0x07,
+            0x0800..=0x0BFF => self.ram_banks[1] = value & 0x07,
+            0x0C00..=0x0FFF => self.flash_enabled = value & 0x01 != 0,
+            0x1000 => self.flash_write_enabled = value & 0x01 != 0,
+            0x2000..=0x27FF => self.rom_banks[0] = value & 0x7F,
+            0x2800..=0x2FFF => self.flash_selected[0] = value == 0x08,
+            0x3000..=0x37FF => self.rom_banks[1] = value & 0x7F,
+            0x3800..=0x3FFF => self.flash_selected[1] = value == 0x08,
+            0x4000..=0x7FFF => {
+                let half = (address as usize - 0x4000) / ROM_HALF_BANK_SIZE;
+                if self.flash_selected[half] && self.flash_enabled && self.flash_write_enabled {
+                    let offset = self.flash_offset(half, address);
+                    self.flash.write(offset, value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn read_ram(&mut self, address: u16) -> u8 {
+        match self.ram_offset(address) {
+            Some(offset) => self.ram[offset],
+            None => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        if let Some(offset) = self.ram_offset(address) {
+            self.ram[offset] = value;
+        }
+    }
+
+    // The flash is saved after the RAM.
+    fn save_data(&mut self) -> Vec<u8> {
+        let mut data = self.ram.clone();
+        data.extend_from_slice(&self.flash.data);
+        data
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        let ram_size = self.ram.len().min(data.len());
+        self.ram[..ram_size].copy_from_slice(&data[..ram_size]);
+        let flash = &data[ram_size..];
+        let flash_size = FLASH_SIZE.min(flash.len());
+        self.flash.data[..flash_size].copy_from_slice(&flash[..flash_size]);
+    }
+}
@@ -0,0 +1,300 @@
+use crate::cartridge::{banked_byte, Mapper, ROM_BANK_SIZE};
+
+/// The tilt of the cartridge, in g along the x (right) and y (down) axes.
+pub trait Accelerometer {
+    fn tilt(&mut self) -> (f32, f32);
+}
+
+impl<F: FnMut() -> (f32, f32)> Accelerometer for F {
+    fn tilt(&mut self) -> (f32, f32) {
+        self()
+    }
+}
+
+// What the sensor reads when level, and how far 1g moves it.
+const ACCELEROMETER_CENTER: f32 = 0x81D0 as f32;
+const ACCELEROMETER_GRAVITY: f32 = 0x70 as f32;
+const ACCELEROMETER_UNLATCHED: u16 = 0x8000;
+
+const EEPROM_WORDS: usize = 128;
+
+// EEPROM register bits.
+const EEPROM_DO: u8 = 0x01;
+const EEPROM_DI: u8 = 0x02;
+const EEPROM_CLK: u8 = 0x40;
+const EEPROM_CS: u8 = 0x80;
+
+#[derive(Clone, Copy)]
+enum EepromState {
+    Command,
+    Reading { word: u16, remaining: u8 },
+    Writing { address: Option<u8>, remaining: u8 },
+}
+
+/// A 93LC56 serial EEPROM in 16-bit mode: 128 words, driven one bit at a
+/// time on the rising edge of CLK while CS is high. Writes complete
+/// immediately, so DO always signals ready afterwards.
+struct Eeprom {
+    words: [u16; EEPROM_WORDS],
+    cs: bool,
+    clk: bool,
+    di: bool,
+    data_out: bool,
+    shift: u16,
+    bits: u8,
+    write_enabled: bool,
+    state: EepromState,
+}
+
+impl Eeprom {
+    fn new() -> Eeprom {
+        Eeprom {
+            words: [0xFFFF; EEPROM_WORDS],
+            cs: false,
+            clk: false,
+            di: false,
+            data_out: true,
+            shift: 0,
+            bits: 0,
+            write_enabled: false,
+            state: EepromState::Command,
+        }
+    }
+
+    fn read(&self) -> u8 {
+        let mut value = 0;
+        if self.data_out {
+            value |= EEPROM_DO;
+        }
+        if self.di {
+            value |= EEPROM_DI;
+        }
+        if self.clk {
+            value |= EEPROM_CLK;
+        }
+        if self.cs {
+            value |= EEPROM_CS;
+        }
+        value
+    }
+
+    fn write(&mut self, value: u8) {
+        let cs = value & EEPROM_CS != 0;
+        let clk = value & EEPROM_CLK != 0;
+        self.di = value & EEPROM_DI != 0;
+        if !cs {
+            self.state = EepromState::Command;
+            self.shift = 0;
+            self.bits = 0;
+        } else if clk && !self.clk {
+            self.clock_in();
+        }
+        self.cs = cs;
+        self.clk = clk;
+    }
+
+    fn clock_in(&mut self) {
+        match self.state {
+            EepromState::Command => {
+                // wait for the start bit
+                if self.bits == 0 && !self.di {
+                    return;
+                }
+                self.shift = self.shift << 1 | self.di as u16;
+                self.bits += 1;
+                // start bit, 2 opcode bits and 8 address bits
+                if self.bits == 11 {
+                    self.command(self.shift);
+                    self.shift = 0;
+                    self.bits = 0;
+                }
+            }
+            EepromState::Reading { word, remaining } => {
+                self.data_out = word & 0x8000 != 0;
+                self.state = if remaining > 1 {
+                    EepromState::Reading {
+                        word: word << 1,
+                        remaining: remaining - 1,
+                    }
+                } else {
+                    EepromState::Command
+                };
+            }
+            EepromState::Writing { address, remaining } => {
+                self.shift = self.shift << 1 | self.di as u16;
+                if remaining > 1 {
+                    self.state = EepromState::Writing {
+                        address,
+                        remaining: remaining - 1,
+                    };
+                    return;
+                }
+                if self.write_enabled {
+                    match address {
+                        Some(address) => self.words[address as usize] = self.shift,
+                        None => self.words.fill(self.shift),
+                    }
+                }
+                self.data_out = true;
+                self.shift = 0;
+                self.state = EepromState::Command;
+            }
+        }
+    }
+
+    fn command(&mut self, command: u16) {
+        let address = (command & 0x7F) as u8;
+        self.state = match (command >> 8) & 0x03 {
+            0b10 => {
+                self.data_out = false;
+                EepromState::Reading {
+                    word: self.words[address as usize],
+                    remaining: 16,
+                }
+            }
+            0b01 => EepromState::Writing {
+                address: Some(address),
+                remaining: 16,
+            },
+            0b11 => {
+                if self.write_enabled {
+                    self.words[address as usize] = 0xFFFF;
+                }
+                self.data_out = true;
+                EepromState::Command
+            }
+            _ => match (command >> 6) & 0x03 {
+                0b11 => {
+                    self.write_enabled = true;
+                    EepromState::Command
+                }
+                0b00 => {
+                    self.write_enabled = false;
+                    EepromState::Command
+                }
+                0b10 => {
+                    if self.write_enabled {
+                        self.words.fill(0xFFFF);
+                    }
+                    self.data_out = true;
+                    EepromState::Command
+                }
+                _ => EepromState::Writing {
+                    address: None,
+                    remaining: 16,
+                },
+            },
+        };
+    }
+}
+
+/// MBC7, with a two-axis accelerometer and an EEPROM in place of RAM. Both
+/// are reached through registers at A000-AFFF, selected by address bits 4-7.
+pub struct Mbc7 {
+    rom: Vec<u8>,
+    rom_bank: u8,
+    ram_enabled: [bool; 2], // both halves are needed to reach the registers
+    accelerometer: Option<Box<dyn Accelerometer>>,
+    latched: (u16, u16),
+    latch_erased: bool,
+    eeprom: Eeprom,
+}
+
+impl Mbc7 {
+    pub fn new(rom: Vec<u8>) -> Mbc7 {
+        Mbc7 {
+            rom,
+            rom_bank: 1,
+            ram_enabled: [false, false],
+            accelerometer: None,
+            latched: (ACCELEROMETER_UNLATCHED, ACCELEROMETER_UNLATCHED),
+            latch_erased: false,
+            eeprom: Eeprom::new(),
+        }
+    }
+
+    fn registers_enabled(&self) -> bool {
+        self.ram_enabled[0] && self.ram_enabled[1]
+    }
+
+    fn latch(&mut self) {
+        let (x, y) = match self.accelerometer.as_mut() {
+            Some(accelerometer) => accelerometer.tilt(),
+            None => (0.0, 0.0),
+        };
+        let reading = |g: f32| (ACCELEROMETER_CENTER + g * ACCELEROMETER_GRAVITY) as u16;
+        self.latched = (reading(x), reading(y));
+    }
+}
+
+impl Mapper for Mbc7 {
+    fn read_rom(&mut self, address: u16) -> u8 {
+        match address {
+            0x0000..=0x3FFF => banked_byte(&self.rom, ROM_BANK_SIZE, 0, address),
+            _ => banked_byte(&self.rom, ROM_BANK_SIZE, self.rom_bank as usize, address),
+        }
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled[0] = value == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = value & 0x7F,
+            0x4000..=0x5FFF => self.ram_enabled[1] = value == 0x40,
+            _ => {}
+        }
+    }
+
+    fn read_ram(&mut self, address: u16) -> u8 {
+        if !self.registers_enabled() || address >= 0x1000 {
+            return 0xFF;
+        }
+        let (x, y) = self.latched;
+        match (address >> 4) & 0x0F {
+            0x2 => x as u8,
+            0x3 => (x >> 8) as u8,
+            0x4 => y as u8,
+            0x5 => (y >> 8) as u8,
+            0x6 => 0x00, // there's no z axis
+            0x8 => self.eeprom.read(),
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        if !self.registers_enabled() || address >= 0x1000 {
+            return;
+        }
+        match (address >> 4) & 0x0F {
+            // 0x55 then 0xAA takes a new reading
+            0x0 if value == 0x55 => {
+                self.latched = (ACCELEROMETER_UNLATCHED, ACCELEROMETER_UNLATCHED);
+                self.latch_erased = true;
+            }
+            0x1 if value == 0xAA && self.latch_erased => {
+                self.latch();
+                self.latch_erased = false;
+            }
+            0x8 => self.eeprom.write(value),
+            _ => {}
+        }
+    }
+
+    // The EEPROM is saved as little endian words.
+    fn save_data(&mut self) -> Vec<u8> {
+        self.eeprom
+            .words
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        for (word, bytes) in self.eeprom.words.iter_mut().zip(data.chunks_exact(2)) {
+            *word = u16::from_le_bytes([bytes[0], bytes[1]]);
+        }
+    }
+
+    fn set_accelerometer(&mut self, accelerometer: Box<dyn Accelerometer>) {
+        self.accelerometer = Some(accelerometer);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::cartridge::{banked_byte, Mapper, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+/// MMM01 multicarts. The controller starts out unmapped, showing the menu in
+/// the last 32 KiB of the ROM. The menu then picks a game by setting the
+/// outer bank bits and which ROM bank bits stay fixed, and maps it in by
+/// setting bit 6 of the RAM enable register. From then on it behaves like an
+/// MBC1 confined to that game, until the next reset.
+pub struct Mmm01 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    mapped: bool,
+    ram_enabled: bool,
+    rom_bank_low: u8,  // 5 bits
+    rom_bank_mid: u8,  // 2 bits, only writable while unmapped
+    rom_bank_high: u8, // 2 bits, only writable while unmapped
+    rom_bank_mask: u8, // bits of rom_bank_low that can't be changed once mapped
+    ram_bank_low: u8,  // 2 bits
+    ram_bank_high: u8, // 2 bits, only writable while unmapped
+    advanced_banking: bool,
+}
+
+impl Mmm01 {
+    pub fn new(rom: Vec<u8>, ram_size: usize) -> Mmm01 {
+        Mmm01 {
+            rom,
+            ram: vec![0; ram_size],
+            mapped: false,
+            ram_enabled: false,
+            rom_bank_low: 0,
+            rom_bank_mid: 0,
+            rom_bank_high: 0,
+            rom_bank_mask: 0,
+            ram_bank_low: 0,
+            ram_bank_high: 0,
+            advanced_banking: false,
+        }
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        self.mapped
+    }
+
+    fn outer_bank(&self) -> usize {
+        (self.rom_bank_high as usize) << 7 | (self.rom_bank_mid as usize) << 5
+    }
+
+    fn rom_bank_0(&self) -> usize {
+        self.outer_bank() | (self.rom_bank_low & self.rom_bank_mask) as usize
+    }
+
+    fn rom_bank_n(&self) -> usize {
+        // as on the MBC1, 0 in the writable bits selects 1
+        let mut low = self.rom_bank_low;
+        if low & !self.rom_bank_mask == 0 {
+            low |= 1;
+        }
+        self.outer_bank() | low as usize
+    }
+
+    fn ram_offset(&self, address: u16) -> Option<usize> {
+        if !self.ram_enabled || self.ram.is_empty() {
+            return None;
+        }
+        let low = if self.advanced_banking {
+            self.ram_bank_low
+        } else {
+            0
+        };
+        let bank = (self.ram_bank_high << 2 | low) as usize;
+        Some((bank * RAM_BANK_SIZE + address as usize) % self.ram.len())
+    }
+}
+
+impl Mapper for Mmm01 {
+    fn read_rom(&mut self, address: u16) -> u8 {
+        let bank = if !self.mapped {
+            // the upper address lines are pulled high, showing the last 32 KiB
+            let banks = (self.rom.len() / ROM_BANK_SIZE).max(2);
+            match address {
+                0x0000..=0x3FFF => banks - 2,
+                _ => banks - 1,
+            }
+        } else {
+            match address {
+                0x0000..=0x3FFF => self.rom_bank_0(),
+                _ => self.rom_bank_n(),
+            }
+        };
+        banked_byte(&self.rom, ROM_BANK_SIZE, bank, address)
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => {
+                self.ram_enabled = value & 0x0F == 0x0A;
+                if !self.mapped && value & 0x40 != 0 {
+                    self.mapped = true;
+                }
+            }
+            0x2000..=0x3FFF => {
+                let writable = if self.mapped {
+                    !self.rom_bank_mask & 0x1F
+                } else {
+                    0x1F
+                };
+                self.rom_bank_low = self.rom_bank_low & !writable | value & writable;
+                if !self.mapped {
+                    self.rom_bank_mid = (value >> 5) & 0x03;
+                }
+            }
+            0x4000..=0x5FFF => {
+                self.ram_bank_low = value & 0x03;
+                if !self.mapped {
+                    self.ram_bank_high = (value >> 2) & 0x03;
+                    self.rom_bank_high = (value >> 4) & 0x03;
+                }
+            }
+            _ => {
+                self.advanced_banking = value & 0x01 != 0;
+                if !self.mapped {
+                    // bits 2-5 fix bits 1-4 of the ROM bank
+                    self.rom_bank_mask = (value >> 1) & 0x1E;
+                }
+            }
+        }
+    }
+
+    fn read_ram(&mut self, address: u16) -> u8 {
+        match self.ram_offset(address) {
+            Some(offset) => self.ram[offset],
+            None => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        if let Some(offset) = self.ram_offset(address) {
+            self.ram[offset] = value;
+        }
+    }
+
+    fn save_data(&mut self) -> Vec<u8> {
+        self.ram.clone()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        let size = self.ram.len().min(data.len());
+        self.ram[..size].copy_from_slice(&data[..size]);
+    }
+}
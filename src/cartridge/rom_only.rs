@@ -0,0 +1,43 @@
+use crate::cartridge::Mapper;
+
+/// 32 KiB of ROM mapped straight in, optionally with up to 8 KiB of RAM.
+pub struct RomOnly {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+}
+
+impl RomOnly {
+    pub fn new(rom: Vec<u8>, ram_size: usize) -> RomOnly {
+        RomOnly {
+            rom,
+            ram: vec![0; ram_size.min(0x2000)],
+        }
+    }
+}
+
+impl Mapper for RomOnly {
+    fn read_rom(&mut self, address: u16) -> u8 {
+        self.rom.get(address as usize).copied().unwrap_or(0xFF)
+    }
+
+    fn write_rom(&mut self, _address: u16, _value: u8) {}
+
+    fn read_ram(&mut self, address: u16) -> u8 {
+        self.ram.get(address as usize).copied().unwrap_or(0xFF)
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        if let Some(byte) = self.ram.get_mut(address as usize) {
+            *byte = value;
+        }
+    }
+
+    fn save_data(&mut self) -> Vec<u8> {
+        self.ram.clone()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        let size = self.ram.len().min(data.len());
+        self.ram[..size].copy_from_slice(&data[..size]);
+    }
+}
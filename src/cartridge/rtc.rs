@@ -0,0 +1,227 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where the RTC gets the time from, in whole seconds since some fixed point.
+pub trait Clock {
+    fn now(&self) -> u64;
+}
+
+/// Wall clock time.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0)
+    }
+}
+
+/// A clock that only moves when told to. Clones share the same time, so a
+/// test can keep one and hand another to the cartridge.
+#[derive(Clone, Default)]
+pub struct VirtualClock {
+    seconds: Rc<Cell<u64>>,
+}
+
+impl VirtualClock {
+    pub fn new(seconds: u64) -> VirtualClock {
+        VirtualClock {
+            seconds: Rc::new(Cell::new(seconds)),
+        }
+    }
+
+    pub fn advance(&self, seconds: u64) {
+        self.seconds.set(self.seconds.get() + seconds);
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> u64 {
+        self.seconds.get()
+    }
+}
+
+pub const RTC_SECONDS: u8 = 0x08;
+pub const RTC_MINUTES: u8 = 0x09;
+pub const RTC_HOURS: u8 = 0x0A;
+pub const RTC_DAY_LOW: u8 = 0x0B;
+pub const RTC_DAY_HIGH: u8 = 0x0C;
+
+const DAY_HIGH_BIT: u8 = 0x01;
+const HALT_BIT: u8 = 0x40;
+const CARRY_BIT: u8 = 0x80;
+
+// Size of the RTC state appended to save files, in the layout BGB and VBA-M use.
+pub const RTC_SAVE_SIZE: usize = 48;
+
+/// The MBC3 real-time clock. Time is only brought up to date when the
+/// registers are touched, by looking at how far the clock has moved since.
+pub struct Rtc {
+    clock: Box<dyn Clock>,
+    last_update: u64,
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    days: u16, // 9 bits
+    halted: bool,
+    day_carry: bool,
+    latched: [u8; 5],
+    latch_armed: bool,
+}
+
+impl Rtc {
+    pub fn new(clock: Box<dyn Clock>) -> Rtc {
+        let last_update = clock.now();
+        Rtc {
+            clock,
+            last_update,
+            seconds: 0,
+            minutes: 0,
+            hours: 0,
+            days: 0,
+            halted: false,
+            day_carry: false,
+            latched: [0; 5],
+            latch_armed: false,
+        }
+    }
+
+    /// Reads a latched register (0x08-0x0C).
+    pub fn read(&self, register: u8) -> u8 {
+        self.latched[(register - RTC_SECONDS) as usize]
+    }
+
+    /// Writes a live register (0x08-0x0C). Only the bits that exist are kept.
+    pub fn write(&mut self, register: u8, value: u8) {
+        self.update();
+        match register {
+            RTC_SECONDS => self.seconds = value & 0x3F,
+            RTC_MINUTES => self.minutes = value & 0x3F,
+            RTC_HOURS => self.hours = value & 0x1F,
+            RTC_DAY_LOW => self.days = self.days & 0x100 | value as u16,
+            _ => {
+                self.days = self.days & 0xFF | ((value & DAY_HIGH_BIT) as u16) << 8;
+                self.halted = value & HALT_BIT != 0;
+                self.day_carry = value & CARRY_BIT != 0;
+            }
+        }
+    }
+
+    /// Writing 0x00 and then 0x01 to 6000-7FFF copies the live registers into
+    /// the ones that can be read.
+    pub fn write_latch(&mut self, value: u8) {
+        if self.latch_armed && value == 0x01 {
+            self.update();
+            self.latched = self.registers();
+        }
+        self.latch_armed = value == 0x00;
+    }
+
+    fn registers(&self) -> [u8; 5] {
+        [
+            self.seconds,
+            self.minutes,
+            self.hours,
+            self.days as u8,
+            (self.days >> 8) as u8 & DAY_HIGH_BIT
+                | if self.halted { HALT_BIT } else { 0 }
+                | if self.day_carry { CARRY_BIT } else { 0 },
+        ]
+    }
+
+    fn update(&mut self) {
+        let now = self.clock.now();
+        let elapsed = now.saturating_sub(self.last_update);
+        self.last_update = now;
+        if !self.halted {
+            self.advance(elapsed);
+        }
+    }
+
+    fn advance(&mut self, mut seconds: u64) {
+        // registers set out of range count up to their bit width before
+        // wrapping to 0, without carrying, so step through those one by one
+        while seconds > 0 && (self.seconds >= 60 || self.minutes >= 60 || self.hours >= 24) {
+            self.tick();
+            seconds -= 1;
+        }
+        if seconds == 0 {
+            return;
+        }
+
+        let total = self.seconds as u64
+            + self.minutes as u64 * 60
+            + self.hours as u64 * 3600
+            + self.days as u64 * 86400
+            + seconds;
+        self.seconds = (total % 60) as u8;
+        self.minutes = (total / 60 % 60) as u8;
+        self.hours = (total / 3600 % 24) as u8;
+        let days = total / 86400;
+        if days >= 512 {
+            self.day_carry = true;
+        }
+        self.days = (days % 512) as u16;
+    }
+
+    fn tick(&mut self) {
+        self.seconds = (self.seconds + 1) & 0x3F;
+        if self.seconds != 60 {
+            return;
+        }
+        self.seconds = 0;
+        self.minutes = (self.minutes + 1) & 0x3F;
+        if self.minutes != 60 {
+            return;
+        }
+        self.minutes = 0;
+        self.hours = (self.hours + 1) & 0x1F;
+        if self.hours != 24 {
+            return;
+        }
+        self.hours = 0;
+        self.days += 1;
+        if self.days == 512 {
+            self.days = 0;
+            self.day_carry = true;
+        }
+    }
+
+    /// The clock state as appended to save files: live then latched registers
+    /// as 32-bit little endian values, followed by a 64-bit timestamp.
+    pub fn save(&mut self) -> [u8; RTC_SAVE_SIZE] {
+        self.update();
+        let mut data = [0; RTC_SAVE_SIZE];
+        let registers = self.registers();
+        for (i, value) in registers.iter().chain(self.latched.iter()).enumerate() {
+            data[i * 4] = *value;
+        }
+        data[40..48].copy_from_slice(&self.last_update.to_le_bytes());
+        data
+    }
+
+    /// Restores state written by `save`, catching up on the time that passed
+    /// since. `data` can also be the 44 byte variant with a 32-bit timestamp.
+    pub fn load(&mut self, data: &[u8]) {
+        if data.len() < 44 {
+            return;
+        }
+        self.seconds = data[0] & 0x3F;
+        self.minutes = data[4] & 0x3F;
+        self.hours = data[8] & 0x1F;
+        self.days = data[12] as u16 | ((data[16] & DAY_HIGH_BIT) as u16) << 8;
+        self.halted = data[16] & HALT_BIT != 0;
+        self.day_carry = data[16] & CARRY_BIT != 0;
+        for i in 0..5 {
+            self.latched[i] = data[20 + i * 4];
+        }
+        let mut timestamp = [0; 8];
+        let timestamp_size = (data.len() - 40).min(8);
+        timestamp[..timestamp_size].copy_from_slice(&data[40..40 + timestamp_size]);
+        self.last_update = u64::from_le_bytes(timestamp);
+        self.update();
+    }
+}
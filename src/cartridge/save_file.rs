@@ -0,0 +1,98 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::cartridge::{Accelerometer, ImageSensor, InfraredPort, Mapper, RumbleHandler};
+
+/// Keeps a mapper's battery-backed memory in a `.sav` file: the external RAM
+/// as is, followed by the clock state for cartridges with one. That's the
+/// layout BGB, VBA-M and most other emulators use, so their saves can be
+/// loaded too, including the older 44 byte RTC footer. The file is written
+/// on `flush` and when the mapper is dropped.
+pub struct SaveFile {
+    mapper: Box<dyn Mapper>,
+    path: PathBuf,
+}
+
+/// The contents of the save file at `path`, or `None` if there isn't one yet.
+pub fn read_save(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+impl SaveFile {
+    /// Wraps `mapper`, which should already hold anything that was saved at
+    /// `path`; see `read_save`.
+    pub fn new(mapper: Box<dyn Mapper>, path: impl Into<PathBuf>) -> SaveFile {
+        SaveFile {
+            mapper,
+            path: path.into(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Mapper for SaveFile {
+    fn read_rom(&mut self, address: u16) -> u8 {
+        self.mapper.read_rom(address)
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        self.mapper.write_rom(address, value)
+    }
+
+    fn read_ram(&mut self, address: u16) -> u8 {
+        self.mapper.read_ram(address)
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        self.mapper.write_ram(address, value)
+    }
+
+    fn save_data(&mut self) -> Vec<u8> {
+        self.mapper.save_data()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        self.mapper.load_save_data(data)
+    }
+
+    // Written to a temporary file first so a crash halfway through can't
+    // leave a truncated save behind.
+    fn flush(&mut self) -> io::Result<()> {
+        let data = self.mapper.save_data();
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+        fs::write(&temporary, data)?;
+        fs::rename(&temporary, &self.path)
+    }
+
+    fn set_rumble_handler(&mut self, handler: RumbleHandler) {
+        self.mapper.set_rumble_handler(handler)
+    }
+
+    fn set_infrared_port(&mut self, port: Box<dyn InfraredPort>) {
+        self.mapper.set_infrared_port(port)
+    }
+
+    fn set_accelerometer(&mut self, accelerometer: Box<dyn Accelerometer>) {
+        self.mapper.set_accelerometer(accelerometer)
+    }
+
+    fn set_image_sensor(&mut self, sensor: Box<dyn ImageSensor>) {
+        self.mapper.set_image_sensor(sensor)
+    }
+}
+
+impl Drop for SaveFile {
+    fn drop(&mut self) {
+        // nowhere to report a failure from here; call flush to find out
+        let _ = self.flush();
+    }
+}
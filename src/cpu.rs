@@ -1,17 +1,43 @@
+pub mod decoder;
 pub mod instructions;
 pub mod registers;
+pub mod timing;
 
 use std::ops::{BitAnd, BitOr, BitXor, Not};
 
-use crate::cpu::instructions::{ArithmeticTarget16, ArithmeticTarget8, Instruction};
+use crate::bus::{Bus, FlatRam};
+use crate::cpu::decoder::{decode, instruction_length, DecodeError};
+use crate::cpu::instructions::{
+    ArithmeticTarget16, ArithmeticTarget8, Condition, IndirectTarget, Instruction,
+};
 use crate::cpu::registers::{FlagsRegister, Registers};
+use crate::interrupts::{
+    Interrupt, INTERRUPT_ENABLE_ADDRESS, INTERRUPT_FLAG_ADDRESS, INTERRUPT_MASK,
+};
 
-pub struct CPU {
+const STOP_OPCODE: u8 = 0x10;
+
+// M-cycles taken to push pc and jump to an interrupt vector.
+const INTERRUPT_DISPATCH_CYCLES: u8 = 5;
+
+pub struct CPU<B: Bus = FlatRam> {
     pub registers: Registers,
-    pc: u16,
+    pub pc: u16,
+    pub bus: B,
+    pub ime: bool, // interrupt master enable
+    pub halted: bool,
+    pub stopped: bool,
+    ime_delay: u8,
+    halt_bug: bool,
+    branch_taken: bool,
+    cycles_ticked: u8,
 }
 
 pub fn new_cpu() -> CPU {
+    new_cpu_with_bus(FlatRam::new())
+}
+
+pub fn new_cpu_with_bus<B: Bus>(bus: B) -> CPU<B> {
     CPU {
         registers: Registers {
             a: 0,
@@ -30,15 +56,138 @@ pub fn new_cpu() -> CPU {
             sp: 0, // TODO: 02/09/2023 (jps): this is probably the wrong value
         },
         pc: 0,
+        bus,
+        ime: false,
+        halted: false,
+        stopped: false,
+        ime_delay: 0,
+        halt_bug: false,
+        branch_taken: false,
+        cycles_ticked: 0,
     }
 }
 
-impl CPU {
-    pub fn test_run(&mut self) {
-        self.execute(Instruction::ADDr(ArithmeticTarget8::A));
-        self.execute(Instruction::ADDi(3));
-        self.execute(Instruction::ADCr(ArithmeticTarget8::B));
-        self.execute(Instruction::ADCi(2));
+impl<B: Bus> CPU<B> {
+    /// Services the highest priority pending interrupt if IME is set, otherwise
+    /// fetches the instruction at `pc`, advances `pc` past it and executes it.
+    /// Returns the number of M-cycles taken. While halted or stopped each step
+    /// idles for a single M-cycle; in STOP only the joypad sees that cycle.
+    ///
+    /// The bus is ticked once per M-cycle: every memory access takes a cycle of
+    /// its own, and any internal cycles left over are ticked once the
+    /// instruction is done.
+    pub fn step(&mut self) -> Result<u8, DecodeError> {
+        self.cycles_ticked = 0;
+        let cycles = self.run_next()?;
+        while self.cycles_ticked < cycles {
+            self.tick();
+        }
+        Ok(cycles)
+    }
+
+    fn run_next(&mut self) -> Result<u8, DecodeError> {
+        if self.stopped {
+            // TODO: STOP should also switch speeds on CGB when KEY1 asks for it
+            let requested = self.bus.read(INTERRUPT_FLAG_ADDRESS);
+            if requested & Interrupt::Joypad.mask() == 0 {
+                self.bus.tick_stopped();
+                self.cycles_ticked += 1;
+                return Ok(1);
+            }
+            self.stopped = false;
+        }
+
+        let pending = self.pending_interrupt();
+        if self.halted {
+            // any enabled interrupt wakes the CPU up, whether or not IME is set
+            if pending.is_none() {
+                return Ok(1);
+            }
+            self.halted = false;
+        }
+
+        if let Some(interrupt) = pending {
+            if self.ime {
+                self.dispatch_interrupt(interrupt);
+                return Ok(INTERRUPT_DISPATCH_CYCLES);
+            }
+        }
+
+        let instruction = self.fetch()?;
+        self.branch_taken = false;
+        self.execute(instruction);
+        self.tick_ime_delay();
+        Ok(timing::cycles(&instruction, self.branch_taken))
+    }
+
+    /// Sets `interrupt`'s bit in IF through the bus.
+    pub fn request_interrupt(&mut self, interrupt: Interrupt) {
+        let flags = self.bus.read(INTERRUPT_FLAG_ADDRESS);
+        self.bus
+            .write(INTERRUPT_FLAG_ADDRESS, flags | interrupt.mask());
+    }
+
+    // IE and IF are wired straight into the CPU, so looking at them doesn't
+    // cost any bus cycles
+    fn pending_interrupt(&mut self) -> Option<Interrupt> {
+        let enabled = self.bus.read(INTERRUPT_ENABLE_ADDRESS);
+        let requested = self.bus.read(INTERRUPT_FLAG_ADDRESS);
+        Interrupt::highest_priority(enabled & requested & INTERRUPT_MASK)
+    }
+
+    fn dispatch_interrupt(&mut self, interrupt: Interrupt) {
+        self.ime = false;
+        self.ime_delay = 0;
+        // an interrupt straight after a bugged HALT (EI; HALT) returns to the
+        // HALT itself rather than re-reading the byte after it
+        if self.halt_bug {
+            self.halt_bug = false;
+            self.pc = self.pc.wrapping_sub(1);
+        }
+        let flags = self.bus.read(INTERRUPT_FLAG_ADDRESS);
+        self.bus
+            .write(INTERRUPT_FLAG_ADDRESS, flags & !interrupt.mask());
+        self.call(interrupt.vector());
+    }
+
+    // EI only takes effect after the instruction following it has executed
+    fn tick_ime_delay(&mut self) {
+        if self.ime_delay > 0 {
+            self.ime_delay -= 1;
+            if self.ime_delay == 0 {
+                self.ime = true;
+            }
+        }
+    }
+
+    /// Steps until at least `cycles` M-cycles have elapsed. Returns how many
+    /// actually did, which can overshoot by up to one instruction.
+    pub fn run_for_cycles(&mut self, cycles: u64) -> Result<u64, DecodeError> {
+        let mut elapsed = 0;
+        while elapsed < cycles {
+            elapsed += self.step()? as u64;
+        }
+        Ok(elapsed)
+    }
+
+    fn fetch(&mut self) -> Result<Instruction, DecodeError> {
+        // the HALT bug stops pc from being incremented after the opcode, so the
+        // opcode byte is read again as the first operand (or next opcode)
+        let skip = if self.halt_bug { 1 } else { 0 };
+        self.halt_bug = false;
+
+        let mut bytes = [0; 3];
+        bytes[0] = self.read_memory(self.pc);
+        let length = instruction_length(bytes[0]);
+        // STOP's padding byte is skipped over without being fetched
+        let fetched = if bytes[0] == STOP_OPCODE { 1 } else { length };
+        for offset in 1..fetched {
+            bytes[offset as usize] = self.read_memory(self.pc.wrapping_add(offset - skip));
+        }
+
+        let (instruction, length) = decode(&bytes[..length as usize])?;
+        self.pc = self.pc.wrapping_add(length - skip);
+        Ok(instruction)
     }
 
     pub fn reset(&mut self) {
@@ -57,6 +206,11 @@ impl CPU {
         self.registers.l = 0;
         self.registers.sp = 0;
         self.pc = 0;
+        self.ime = false;
+        self.halted = false;
+        self.stopped = false;
+        self.ime_delay = 0;
+        self.halt_bug = false;
     }
 
     pub fn execute(&mut self, instruction: Instruction) {
@@ -64,6 +218,14 @@ impl CPU {
             // // 8-bit Load instructions
             Instruction::LDrr(dest_reg, src_reg) => self.ld_rr(dest_reg, src_reg),
             Instruction::LDri(dest_reg, value) => self.ld_ri(dest_reg, value),
+            Instruction::LDAind(src) => self.ld_a_ind(src),
+            Instruction::LDindA(dest) => self.ld_ind_a(dest),
+            Instruction::LDAnn(address) => self.ld_a_mem(address),
+            Instruction::LDnnA(address) => self.ld_mem_a(address),
+            Instruction::LDHAn(offset) => self.ld_a_mem(0xFF00 | offset as u16),
+            Instruction::LDHnA(offset) => self.ld_mem_a(0xFF00 | offset as u16),
+            Instruction::LDHAC => self.ld_a_mem(0xFF00 | self.registers.c as u16),
+            Instruction::LDHCA => self.ld_mem_a(0xFF00 | self.registers.c as u16),
 
             // 8 bit arithmetic / logic
             Instruction::ADDr(reg) => self.add_register(reg, false),
@@ -84,12 +246,14 @@ impl CPU {
             Instruction::CPi(value) => self.cp_value(value),
             Instruction::INCr(reg) => self.inc_register(reg),
             Instruction::DECr(reg) => self.dec_register(reg),
+            Instruction::DAA => self.daa(),
             Instruction::CPL => self.cpl(),
 
             /* 16-bit Arithmetic/Logic instructions */
             Instruction::ADDHLRR(reg16) => self.add_hl_rr(reg16),
             Instruction::INCRR(reg16) => self.inc_register16(reg16),
             Instruction::DECRR(reg16) => self.dec_register16(reg16),
+            Instruction::ADDSPe(offset) => self.add_sp_e(offset),
 
             /* Rotate and Shift instructions */
             Instruction::RLCA => self.rotate_a_left(false),
@@ -112,107 +276,145 @@ impl CPU {
 
             /* 16-bit Load instructions */
             Instruction::LDrrnn(dest_reg, value) => self.ld_rrnn(dest_reg, value),
+            Instruction::LDnnSP(address) => self.ld_nn_sp(address),
             Instruction::LDSPHL() => self.ld_sphl(),
+            Instruction::LDHLSPe(offset) => self.ld_hl_sp_e(offset),
             Instruction::PUSH(reg16) => self.push_rr(reg16),
             Instruction::POP(reg16) => self.pop_rr(reg16),
 
+            /* Jump instructions */
+            Instruction::JPnn(address) => self.jump(address),
+            Instruction::JPccnn(cond, address) => self.jump_if(cond, address),
+            Instruction::JPHL => self.jump(self.registers.get_hl()),
+            Instruction::JRe(offset) => self.jump_relative(offset),
+            Instruction::JRcce(cond, offset) => self.jump_relative_if(cond, offset),
+            Instruction::CALLnn(address) => self.call(address),
+            Instruction::CALLccnn(cond, address) => self.call_if(cond, address),
+            Instruction::RET => self.ret(),
+            Instruction::RETcc(cond) => self.ret_if(cond),
+            Instruction::RETI => self.reti(),
+            Instruction::RSTn(vector) => self.call(vector as u16),
+
             // CPU Control instructions
             Instruction::SCF => self.set_carry_flag(),
             Instruction::CCF => self.complement_carry_flag(),
             Instruction::NOP => {}
-            Instruction::HALT => { /* todo later */ }
+            Instruction::HALT => self.halt(),
+            Instruction::STOP => self.stop(),
+            Instruction::DI => self.disable_interrupts(),
+            Instruction::EI => self.enable_interrupts(),
         }
     }
 
     fn add_register(&mut self, reg: ArithmeticTarget8, with_carry: bool) {
-        self.add_value(self.read_register(reg), with_carry);
+        let value = self.read_register(reg);
+        self.add_value(value, with_carry);
     }
 
     fn add_value(&mut self, value: u8, with_carry: bool) {
-        let mut new_value = self.add(value);
+        let carry = (with_carry && self.registers.f.carry) as u8;
+        let a = self.registers.a;
+        let new_value = a.wrapping_add(value).wrapping_add(carry);
+        self.registers.f.set(
+            new_value == 0,
+            false,
+            (a & 0x0F) + (value & 0x0F) + carry > 0x0F,
+            a as u16 + value as u16 + carry as u16 > 0xFF,
+        );
         self.registers.a = new_value;
-        if with_carry && self.registers.f.carry {
-            new_value = self.add(1);
-            self.registers.a = new_value;
-        }
-    }
-
-    fn add(&mut self, value: u8) -> u8 {
-        let (new_value, did_overflow) = self.registers.a.overflowing_add(value);
-        self.registers.set_flags(new_value, did_overflow);
-
-        new_value
     }
 
     fn sub_register(&mut self, reg: ArithmeticTarget8, with_carry: bool) {
-        self.sub_value(self.read_register(reg), with_carry);
+        let value = self.read_register(reg);
+        self.sub_value(value, with_carry);
     }
 
     fn sub_value(&mut self, value: u8, with_carry: bool) {
-        let mut new_value = self.sub(value);
-        self.registers.a = new_value;
-        if with_carry && self.registers.f.carry {
-            new_value = self.sub(1);
-            self.registers.a = new_value;
-        }
-    }
-
-    fn sub(&mut self, value: u8) -> u8 {
-        let (new_value, did_overflow) = self.registers.a.overflowing_sub(value);
-        self.registers.set_flags(new_value, did_overflow);
-
+        self.registers.a = self.sub(value, with_carry);
+    }
+
+    // Sets the flags for A - value (- carry) and returns the result without
+    // storing it, so CP can share it.
+    fn sub(&mut self, value: u8, with_carry: bool) -> u8 {
+        let carry = (with_carry && self.registers.f.carry) as u8;
+        let a = self.registers.a;
+        let new_value = a.wrapping_sub(value).wrapping_sub(carry);
+        self.registers.f.set(
+            new_value == 0,
+            true,
+            (a & 0x0F) < (value & 0x0F) + carry,
+            (a as u16) < value as u16 + carry as u16,
+        );
         new_value
     }
 
     fn and_register(&mut self, reg: ArithmeticTarget8) {
-        self.and_value(self.read_register(reg));
+        let value = self.read_register(reg);
+        self.and_value(value);
     }
 
     fn and_value(&mut self, value: u8) {
         let new_value = self.registers.a.bitand(value);
         self.registers.a = new_value;
+        self.registers.f.set(new_value == 0, false, true, false);
     }
 
     fn xor_register(&mut self, reg: ArithmeticTarget8) {
-        self.xor_value(self.read_register(reg));
+        let value = self.read_register(reg);
+        self.xor_value(value);
     }
 
     fn xor_value(&mut self, value: u8) {
         let new_value = self.registers.a.bitxor(value);
         self.registers.a = new_value;
+        self.registers.f.set(new_value == 0, false, false, false);
     }
 
     fn or_register(&mut self, reg: ArithmeticTarget8) {
-        self.or_value(self.read_register(reg));
+        let value = self.read_register(reg);
+        self.or_value(value);
     }
 
     fn or_value(&mut self, value: u8) {
         let new_value = self.registers.a.bitor(value);
         self.registers.a = new_value;
+        self.registers.f.set(new_value == 0, false, false, false);
     }
 
     fn cp_register(&mut self, reg: ArithmeticTarget8) {
-        self.cp_value(self.read_register(reg));
+        let value = self.read_register(reg);
+        self.cp_value(value);
     }
 
     fn cp_value(&mut self, value: u8) {
-        self.sub(value);
+        self.sub(value, false);
     }
 
+    // INC and DEC are read-modify-write on their operand, not on A
     fn inc_register(&mut self, reg: ArithmeticTarget8) {
-        self.add_register(reg, false);
+        let value = self.read_register(reg);
+        let new_value = value.wrapping_add(1);
+        self.write_register(reg, new_value);
+        self.registers.f.zero = new_value == 0;
+        self.registers.f.subtract = false;
+        self.registers.f.half_carry = value & 0x0F == 0x0F;
     }
 
     fn inc_register16(&mut self, reg: ArithmeticTarget16) {
-        self.write_register16(reg, self.read_register16(reg) + 1)
+        self.write_register16(reg, self.read_register16(reg).wrapping_add(1))
     }
 
     fn dec_register(&mut self, reg: ArithmeticTarget8) {
-        self.sub_register(reg, false);
+        let value = self.read_register(reg);
+        let new_value = value.wrapping_sub(1);
+        self.write_register(reg, new_value);
+        self.registers.f.zero = new_value == 0;
+        self.registers.f.subtract = true;
+        self.registers.f.half_carry = value & 0x0F == 0;
     }
 
     fn dec_register16(&mut self, reg: ArithmeticTarget16) {
-        self.write_register16(reg, self.read_register16(reg) - 1)
+        self.write_register16(reg, self.read_register16(reg).wrapping_sub(1))
     }
 
     fn set_carry_flag(&mut self) {
@@ -224,89 +426,157 @@ impl CPU {
     }
 
     fn ld_rr(&mut self, dest_reg: ArithmeticTarget8, src_reg: ArithmeticTarget8) {
-        self.ld_ri(dest_reg, self.read_register(src_reg));
+        let value = self.read_register(src_reg);
+        self.ld_ri(dest_reg, value);
     }
 
     fn ld_ri(&mut self, dest_reg: ArithmeticTarget8, value: u8) {
         self.write_register(dest_reg, value);
     }
 
+    fn ld_a_ind(&mut self, src: IndirectTarget) {
+        let address = self.indirect_address(src);
+        self.registers.a = self.read_memory(address);
+    }
+
+    fn ld_ind_a(&mut self, dest: IndirectTarget) {
+        let address = self.indirect_address(dest);
+        self.write_memory(address, self.registers.a);
+    }
+
+    fn ld_a_mem(&mut self, address: u16) {
+        self.registers.a = self.read_memory(address);
+    }
+
+    fn ld_mem_a(&mut self, address: u16) {
+        self.write_memory(address, self.registers.a);
+    }
+
+    // (HL+) and (HL-) use the value of HL from before the increment/decrement
+    fn indirect_address(&mut self, target: IndirectTarget) -> u16 {
+        match target {
+            IndirectTarget::BC => self.registers.get_bc(),
+            IndirectTarget::DE => self.registers.get_de(),
+            IndirectTarget::HLInc => {
+                let hl = self.registers.get_hl();
+                self.registers.set_hl(hl.wrapping_add(1));
+                hl
+            }
+            IndirectTarget::HLDec => {
+                let hl = self.registers.get_hl();
+                self.registers.set_hl(hl.wrapping_sub(1));
+                hl
+            }
+        }
+    }
+
+    // Adjusts A back into packed BCD after an addition or subtraction of two
+    // BCD values, using N, H and C to tell which one it was and what carried.
+    fn daa(&mut self) {
+        let mut a = self.registers.a;
+        let mut carry = self.registers.f.carry;
+        if self.registers.f.subtract {
+            if carry {
+                a = a.wrapping_sub(0x60);
+            }
+            if self.registers.f.half_carry {
+                a = a.wrapping_sub(0x06);
+            }
+        } else {
+            if carry || a > 0x99 {
+                a = a.wrapping_add(0x60);
+                carry = true;
+            }
+            if self.registers.f.half_carry || a & 0x0F > 0x09 {
+                a = a.wrapping_add(0x06);
+            }
+        }
+        self.registers.a = a;
+        self.registers.f.zero = a == 0;
+        self.registers.f.half_carry = false;
+        self.registers.f.carry = carry;
+    }
+
     fn cpl(&mut self) {
         self.registers.a = self.registers.a.not();
         self.registers.cpl();
     }
 
+    // ADD HL,rr leaves Z alone and takes H from bit 11
     fn add_hl_rr(&mut self, src_reg: ArithmeticTarget16) {
-        let (new_value, _overflow) = self
-            .registers
-            .get_hl()
-            .overflowing_add(self.read_register16(src_reg));
-        // TODO: 02/09/2023 (jps): does this belong here?
-        let [_, l] = new_value.to_be_bytes();
-        // TODO: 02/09/2023 (jps): not sure this is correct
-        self.registers.set_flags(l, false);
+        let hl = self.registers.get_hl();
+        let value = self.read_register16(src_reg);
+        let (new_value, overflow) = hl.overflowing_add(value);
+        self.registers.set_hl(new_value);
+        self.registers.f.subtract = false;
+        self.registers.f.half_carry = (hl & 0x0FFF) + (value & 0x0FFF) > 0x0FFF;
+        self.registers.f.carry = overflow;
     }
 
     fn rotate_r_left(&mut self, reg: ArithmeticTarget8, with_carry: bool) {
-        let mut val = self.read_register(reg);
-        if with_carry {
-            let chop = val & 1;
-            val = val << 1;
-            if self.registers.f.carry {
-                val = val & 1
-            }
-            self.registers.f.carry = chop == 1;
+        let val = self.read_register(reg);
+        let new_val = if with_carry {
+            val << 1 | self.registers.f.carry as u8
         } else {
-            val = val.rotate_left(1);
-        }
-        self.write_register(reg, val);
+            val.rotate_left(1)
+        };
+        self.write_register(reg, new_val);
+        self.registers
+            .f
+            .set(new_val == 0, false, false, val & 0x80 != 0);
     }
 
     fn rotate_r_right(&mut self, reg: ArithmeticTarget8, with_carry: bool) {
-        let mut val = self.read_register(reg);
-        if with_carry {
-            let chop = val & 1;
-            val = val >> 1;
-            if self.registers.f.carry {
-                val = val | (1 << 7)
-            }
-            self.registers.f.carry = chop == 1;
+        let val = self.read_register(reg);
+        let new_val = if with_carry {
+            val >> 1 | (self.registers.f.carry as u8) << 7
         } else {
-            val = val.rotate_right(1);
-        }
-        self.write_register(reg, val);
+            val.rotate_right(1)
+        };
+        self.write_register(reg, new_val);
+        self.registers
+            .f
+            .set(new_val == 0, false, false, val & 0x01 != 0);
     }
 
+    // unlike their CB-prefixed versions RLCA, RLA, RRCA and RRA always clear Z
     fn rotate_a_left(&mut self, with_carry: bool) {
         self.rotate_r_left(ArithmeticTarget8::A, with_carry);
+        self.registers.f.zero = false;
     }
 
     fn rotate_a_right(&mut self, with_carry: bool) {
         self.rotate_r_right(ArithmeticTarget8::A, with_carry);
+        self.registers.f.zero = false;
     }
 
     fn shift_arithmetic(&mut self, reg: ArithmeticTarget8, left: bool) {
-        let mut val = self.read_register(reg) as i8;
-        if left {
-            val = val << 1
+        let val = self.read_register(reg);
+        let (new_val, carry) = if left {
+            (val << 1, val & 0x80 != 0)
         } else {
-            val = val >> 1
-        }
-        self.write_register(reg, val as u8);
+            // SRA keeps bit 7 as it is
+            (((val as i8) >> 1) as u8, val & 0x01 != 0)
+        };
+        self.write_register(reg, new_val);
+        self.registers.f.set(new_val == 0, false, false, carry);
     }
 
     fn shift_logical(&mut self, reg: ArithmeticTarget8, left: bool) {
-        let mut val = self.read_register(reg);
-        if left {
-            val = val << 1;
+        let val = self.read_register(reg);
+        let (new_val, carry) = if left {
+            (val << 1, val & 0x80 != 0)
         } else {
-            val = val >> 1;
-        }
-        self.write_register(reg, val);
+            (val >> 1, val & 0x01 != 0)
+        };
+        self.write_register(reg, new_val);
+        self.registers.f.set(new_val == 0, false, false, carry);
     }
 
     fn swap_r(&mut self, reg: ArithmeticTarget8) {
-        self.write_register(reg, self.read_register(reg).rotate_left(4));
+        let value = self.read_register(reg).rotate_left(4);
+        self.write_register(reg, value);
+        self.registers.f.set(value == 0, false, false, false);
     }
 
     fn bit_nr(&mut self, which: u8, reg: ArithmeticTarget8) {
@@ -325,7 +595,7 @@ impl CPU {
             panic!("trying to set {} bit of 8 bit register", which);
         }
 
-        let mask = u8::from(1 << which);
+        let mask = 1 << which;
         let val = self.read_register(reg) | mask;
         self.write_register(reg, val);
     }
@@ -344,6 +614,33 @@ impl CPU {
         self.write_register16(reg, value);
     }
 
+    fn ld_nn_sp(&mut self, address: u16) {
+        let [msb, lsb] = self.registers.sp.to_be_bytes();
+        self.write_memory(address, lsb);
+        self.write_memory(address.wrapping_add(1), msb);
+    }
+
+    fn ld_hl_sp_e(&mut self, offset: i8) {
+        let value = self.sp_plus(offset);
+        self.registers.set_hl(value);
+    }
+
+    fn add_sp_e(&mut self, offset: i8) {
+        self.registers.sp = self.sp_plus(offset);
+    }
+
+    // SP+e sets H and C from an unsigned add of the offset to the low byte
+    // of SP, even though the offset itself is signed
+    fn sp_plus(&mut self, offset: i8) -> u16 {
+        let sp = self.registers.sp;
+        let offset_byte = offset as u8;
+        self.registers.f.zero = false;
+        self.registers.f.subtract = false;
+        self.registers.f.half_carry = (sp & 0x0F) + (offset_byte as u16 & 0x0F) > 0x0F;
+        self.registers.f.carry = (sp & 0xFF) + offset_byte as u16 > 0xFF;
+        sp.wrapping_add_signed(offset as i16)
+    }
+
     fn ld_sphl(&mut self) {
         self.write_register16(
             ArithmeticTarget16::SP,
@@ -352,29 +649,130 @@ impl CPU {
     }
 
     fn push_rr(&mut self, reg: ArithmeticTarget16) {
-        let [msb, lsb] = self.read_register16(reg).to_be_bytes();
-        self.registers.sp -= 1;
+        let value = self.read_register16(reg);
+        self.push(value);
+    }
+
+    fn pop_rr(&mut self, reg: ArithmeticTarget16) {
+        let value = self.pop();
+        self.write_register16(reg, value);
+    }
+
+    fn push(&mut self, value: u16) {
+        let [msb, lsb] = value.to_be_bytes();
+        self.registers.sp = self.registers.sp.wrapping_sub(1);
         self.write_memory(self.registers.sp, msb);
-        self.registers.sp -= 1;
+        self.registers.sp = self.registers.sp.wrapping_sub(1);
         self.write_memory(self.registers.sp, lsb);
     }
 
-    fn pop_rr(&mut self, reg: ArithmeticTarget16) {
+    fn pop(&mut self) -> u16 {
         let lsb = self.read_memory(self.registers.sp);
-        self.registers.sp += 1;
+        self.registers.sp = self.registers.sp.wrapping_add(1);
         let msb = self.read_memory(self.registers.sp);
-        self.registers.sp += 1;
-        let value = (msb as u16) << 8 + lsb;
-        self.write_register16(reg, value);
+        self.registers.sp = self.registers.sp.wrapping_add(1);
+        u16::from_be_bytes([msb, lsb])
+    }
+
+    fn condition_met(&mut self, cond: Condition) -> bool {
+        let met = match cond {
+            Condition::NZ => !self.registers.f.zero,
+            Condition::Z => self.registers.f.zero,
+            Condition::NC => !self.registers.f.carry,
+            Condition::C => self.registers.f.carry,
+        };
+        self.branch_taken = met;
+        met
+    }
+
+    fn jump(&mut self, address: u16) {
+        self.pc = address;
+    }
+
+    fn jump_if(&mut self, cond: Condition, address: u16) {
+        if self.condition_met(cond) {
+            self.jump(address);
+        }
+    }
+
+    // the offset is relative to the address of the following instruction,
+    // which is where pc already points
+    fn jump_relative(&mut self, offset: i8) {
+        self.pc = self.pc.wrapping_add_signed(offset as i16);
+    }
+
+    fn jump_relative_if(&mut self, cond: Condition, offset: i8) {
+        if self.condition_met(cond) {
+            self.jump_relative(offset);
+        }
+    }
+
+    fn call(&mut self, address: u16) {
+        self.push(self.pc);
+        self.pc = address;
+    }
+
+    fn call_if(&mut self, cond: Condition, address: u16) {
+        if self.condition_met(cond) {
+            self.call(address);
+        }
+    }
+
+    fn ret(&mut self) {
+        self.pc = self.pop();
+    }
+
+    // With IME clear and an interrupt already pending HALT doesn't halt at
+    // all, and trips the HALT bug instead.
+    fn halt(&mut self) {
+        if !self.ime && self.pending_interrupt().is_some() {
+            self.halt_bug = true;
+        } else {
+            self.halted = true;
+        }
+    }
+
+    fn stop(&mut self) {
+        self.bus.enter_stop();
+        self.stopped = true;
+    }
+
+    fn reti(&mut self) {
+        self.ret();
+        self.ime = true;
+    }
+
+    fn disable_interrupts(&mut self) {
+        self.ime = false;
+        self.ime_delay = 0;
+    }
+
+    fn enable_interrupts(&mut self) {
+        if !self.ime && self.ime_delay == 0 {
+            // counts down at the end of this instruction and the next one
+            self.ime_delay = 2;
+        }
+    }
+
+    fn ret_if(&mut self, cond: Condition) {
+        if self.condition_met(cond) {
+            self.ret();
+        }
+    }
+
+    fn tick(&mut self) {
+        self.bus.tick();
+        self.cycles_ticked = self.cycles_ticked.saturating_add(1);
     }
 
     fn read_memory(&mut self, address: u16) -> u8 {
-        // # TODO: 04/09/2023 (jps): Implement this
-        return 1;
+        self.tick();
+        self.bus.read(address)
     }
 
     fn write_memory(&mut self, address: u16, value: u8) {
-        // # TODO: 04/09/2023 (jps): Implement this
+        self.tick();
+        self.bus.write(address, value);
     }
 
     fn read_register16(&self, reg: ArithmeticTarget16) -> u16 {
@@ -383,6 +781,7 @@ impl CPU {
             ArithmeticTarget16::DE => self.registers.get_de(),
             ArithmeticTarget16::HL => self.registers.get_hl(),
             ArithmeticTarget16::SP => self.registers.sp,
+            ArithmeticTarget16::AF => self.registers.get_af(),
         }
     }
 
@@ -392,10 +791,11 @@ impl CPU {
             ArithmeticTarget16::DE => self.registers.set_de(value),
             ArithmeticTarget16::HL => self.registers.set_hl(value),
             ArithmeticTarget16::SP => self.registers.sp = value,
+            ArithmeticTarget16::AF => self.registers.set_af(value),
         }
     }
 
-    fn read_register(&self, reg: ArithmeticTarget8) -> u8 {
+    fn read_register(&mut self, reg: ArithmeticTarget8) -> u8 {
         match reg {
             ArithmeticTarget8::A => self.registers.a,
             ArithmeticTarget8::B => self.registers.b,
@@ -404,6 +804,7 @@ impl CPU {
             ArithmeticTarget8::E => self.registers.e,
             ArithmeticTarget8::H => self.registers.h,
             ArithmeticTarget8::L => self.registers.l,
+            ArithmeticTarget8::HLI => self.read_memory(self.registers.get_hl()),
         }
     }
 
@@ -430,6 +831,9 @@ impl CPU {
             ArithmeticTarget8::L => {
                 self.registers.l = value;
             }
+            ArithmeticTarget8::HLI => {
+                self.write_memory(self.registers.get_hl(), value);
+            }
         }
     }
 }
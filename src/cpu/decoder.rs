@@ -0,0 +1,246 @@
+use std::fmt;
+
+use crate::cpu::instructions::{
+    ArithmeticTarget16, ArithmeticTarget8, Condition, IndirectTarget, Instruction,
+};
+
+pub const PREFIX_CB: u8 = 0xCB;
+
+// Opcodes that have no instruction assigned to them. Executing any of them
+// locks up the real hardware.
+pub const ILLEGAL_OPCODES: [u8; 11] = [
+    0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    IllegalOpcode(u8),
+    UnexpectedEnd { needed: u16, available: usize },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::IllegalOpcode(opcode) => write!(f, "illegal opcode {:#04X}", opcode),
+            DecodeError::UnexpectedEnd { needed, available } => write!(
+                f,
+                "instruction needs {} bytes but only {} available",
+                needed, available
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Decodes the instruction at the start of `bytes`, returning it together with
+/// its length in bytes (including the 0xCB prefix and any immediate operands).
+pub fn decode(bytes: &[u8]) -> Result<(Instruction, u16), DecodeError> {
+    let opcode = *bytes.first().ok_or(DecodeError::UnexpectedEnd {
+        needed: 1,
+        available: 0,
+    })?;
+    let length = instruction_length(opcode);
+    if bytes.len() < length as usize {
+        return Err(DecodeError::UnexpectedEnd {
+            needed: length,
+            available: bytes.len(),
+        });
+    }
+
+    let instruction = if opcode == PREFIX_CB {
+        decode_prefixed(bytes[1])
+    } else {
+        decode_unprefixed(opcode, bytes)?
+    };
+    Ok((instruction, length))
+}
+
+/// Length in bytes of the instruction starting with `opcode`. This is all that
+/// is needed to know how many bytes to fetch before calling `decode`.
+pub fn instruction_length(opcode: u8) -> u16 {
+    match opcode {
+        PREFIX_CB => 2,
+        // LD rr,nn / LD (nn),SP / LD (nn),A / LD A,(nn)
+        0x01 | 0x11 | 0x21 | 0x31 | 0x08 | 0xEA | 0xFA => 3,
+        // JP / CALL
+        0xC2 | 0xC3 | 0xCA | 0xD2 | 0xDA | 0xC4 | 0xCC | 0xCD | 0xD4 | 0xDC => 3,
+        // LD r,n
+        0x06 | 0x0E | 0x16 | 0x1E | 0x26 | 0x2E | 0x36 | 0x3E => 2,
+        // JR / STOP
+        0x18 | 0x20 | 0x28 | 0x30 | 0x38 | 0x10 => 2,
+        // ALU A,n
+        0xC6 | 0xCE | 0xD6 | 0xDE | 0xE6 | 0xEE | 0xF6 | 0xFE => 2,
+        // LDH / ADD SP,e / LD HL,SP+e
+        0xE0 | 0xF0 | 0xE8 | 0xF8 => 2,
+        _ => 1,
+    }
+}
+
+// Bits 0-2 (source) and 3-5 (destination) of most opcodes select a register
+// in the order B, C, D, E, H, L, (HL), A.
+fn target8(bits: u8) -> ArithmeticTarget8 {
+    match bits & 0b111 {
+        0 => ArithmeticTarget8::B,
+        1 => ArithmeticTarget8::C,
+        2 => ArithmeticTarget8::D,
+        3 => ArithmeticTarget8::E,
+        4 => ArithmeticTarget8::H,
+        5 => ArithmeticTarget8::L,
+        6 => ArithmeticTarget8::HLI,
+        _ => ArithmeticTarget8::A,
+    }
+}
+
+fn target16(bits: u8) -> ArithmeticTarget16 {
+    match bits & 0b11 {
+        0 => ArithmeticTarget16::BC,
+        1 => ArithmeticTarget16::DE,
+        2 => ArithmeticTarget16::HL,
+        _ => ArithmeticTarget16::SP,
+    }
+}
+
+// PUSH and POP use AF where everything else uses SP.
+fn target16_stack(bits: u8) -> ArithmeticTarget16 {
+    match bits & 0b11 {
+        3 => ArithmeticTarget16::AF,
+        other => target16(other),
+    }
+}
+
+fn indirect(bits: u8) -> IndirectTarget {
+    match bits & 0b11 {
+        0 => IndirectTarget::BC,
+        1 => IndirectTarget::DE,
+        2 => IndirectTarget::HLInc,
+        _ => IndirectTarget::HLDec,
+    }
+}
+
+fn condition(bits: u8) -> Condition {
+    match bits & 0b11 {
+        0 => Condition::NZ,
+        1 => Condition::Z,
+        2 => Condition::NC,
+        _ => Condition::C,
+    }
+}
+
+fn decode_unprefixed(opcode: u8, bytes: &[u8]) -> Result<Instruction, DecodeError> {
+    let n = || bytes[1];
+    let e = || bytes[1] as i8;
+    let nn = || u16::from_le_bytes([bytes[1], bytes[2]]);
+    let x = opcode >> 6;
+    let y = (opcode >> 3) & 0b111;
+    let z = opcode & 0b111;
+
+    let instruction = match x {
+        0 => match z {
+            0 => match y {
+                0 => Instruction::NOP,
+                1 => Instruction::LDnnSP(nn()),
+                2 => Instruction::STOP,
+                3 => Instruction::JRe(e()),
+                _ => Instruction::JRcce(condition(y - 4), e()),
+            },
+            1 if y & 1 == 0 => Instruction::LDrrnn(target16(y >> 1), nn()),
+            1 => Instruction::ADDHLRR(target16(y >> 1)),
+            2 if y & 1 == 0 => Instruction::LDindA(indirect(y >> 1)),
+            2 => Instruction::LDAind(indirect(y >> 1)),
+            3 if y & 1 == 0 => Instruction::INCRR(target16(y >> 1)),
+            3 => Instruction::DECRR(target16(y >> 1)),
+            4 => Instruction::INCr(target8(y)),
+            5 => Instruction::DECr(target8(y)),
+            6 => Instruction::LDri(target8(y), n()),
+            _ => match y {
+                0 => Instruction::RLCA,
+                1 => Instruction::RRCA,
+                2 => Instruction::RLA,
+                3 => Instruction::RRA,
+                4 => Instruction::DAA,
+                5 => Instruction::CPL,
+                6 => Instruction::SCF,
+                _ => Instruction::CCF,
+            },
+        },
+        // LD (HL),(HL) is where HALT lives
+        1 if y == 6 && z == 6 => Instruction::HALT,
+        1 => Instruction::LDrr(target8(y), target8(z)),
+        2 => alu_register(y, target8(z)),
+        _ => match opcode {
+            0xC0 | 0xC8 | 0xD0 | 0xD8 => Instruction::RETcc(condition(y)),
+            0xE0 => Instruction::LDHnA(n()),
+            0xE8 => Instruction::ADDSPe(e()),
+            0xF0 => Instruction::LDHAn(n()),
+            0xF8 => Instruction::LDHLSPe(e()),
+            0xC1 | 0xD1 | 0xE1 | 0xF1 => Instruction::POP(target16_stack(y >> 1)),
+            0xC9 => Instruction::RET,
+            0xD9 => Instruction::RETI,
+            0xE9 => Instruction::JPHL,
+            0xF9 => Instruction::LDSPHL(),
+            0xC2 | 0xCA | 0xD2 | 0xDA => Instruction::JPccnn(condition(y), nn()),
+            0xE2 => Instruction::LDHCA,
+            0xEA => Instruction::LDnnA(nn()),
+            0xF2 => Instruction::LDHAC,
+            0xFA => Instruction::LDAnn(nn()),
+            0xC3 => Instruction::JPnn(nn()),
+            0xF3 => Instruction::DI,
+            0xFB => Instruction::EI,
+            0xC4 | 0xCC | 0xD4 | 0xDC => Instruction::CALLccnn(condition(y), nn()),
+            0xC5 | 0xD5 | 0xE5 | 0xF5 => Instruction::PUSH(target16_stack(y >> 1)),
+            0xCD => Instruction::CALLnn(nn()),
+            0xC6 | 0xCE | 0xD6 | 0xDE | 0xE6 | 0xEE | 0xF6 | 0xFE => alu_immediate(y, n()),
+            0xC7 | 0xCF | 0xD7 | 0xDF | 0xE7 | 0xEF | 0xF7 | 0xFF => Instruction::RSTn(y * 8),
+            _ => return Err(DecodeError::IllegalOpcode(opcode)),
+        },
+    };
+    Ok(instruction)
+}
+
+fn alu_register(op: u8, reg: ArithmeticTarget8) -> Instruction {
+    match op {
+        0 => Instruction::ADDr(reg),
+        1 => Instruction::ADCr(reg),
+        2 => Instruction::SUBr(reg),
+        3 => Instruction::SBCr(reg),
+        4 => Instruction::ANDr(reg),
+        5 => Instruction::XORr(reg),
+        6 => Instruction::ORr(reg),
+        _ => Instruction::CPr(reg),
+    }
+}
+
+fn alu_immediate(op: u8, value: u8) -> Instruction {
+    match op {
+        0 => Instruction::ADDi(value),
+        1 => Instruction::ADCi(value),
+        2 => Instruction::SUBi(value),
+        3 => Instruction::SBCi(value),
+        4 => Instruction::ANDi(value),
+        5 => Instruction::XORi(value),
+        6 => Instruction::ORi(value),
+        _ => Instruction::CPi(value),
+    }
+}
+
+fn decode_prefixed(opcode: u8) -> Instruction {
+    let y = (opcode >> 3) & 0b111;
+    let reg = target8(opcode);
+
+    match opcode >> 6 {
+        0 => match y {
+            0 => Instruction::RLCr(reg),
+            1 => Instruction::RRCr(reg),
+            2 => Instruction::RLr(reg),
+            3 => Instruction::RRr(reg),
+            4 => Instruction::SLAr(reg),
+            5 => Instruction::SRAr(reg),
+            6 => Instruction::SWAPr(reg),
+            _ => Instruction::SRLr(reg),
+        },
+        1 => Instruction::BITnr(y, reg),
+        2 => Instruction::RESnr(y, reg),
+        _ => Instruction::SETnr(y, reg),
+    }
+}
@@ -1,4 +1,4 @@
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ArithmeticTarget8 {
     A,
     B,
@@ -7,20 +7,48 @@ pub enum ArithmeticTarget8 {
     E,
     H,
     L,
+    HLI, // (HL), the byte in memory pointed to by HL
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ArithmeticTarget16 {
     BC,
     DE,
     HL,
     SP,
+    AF, // only used by PUSH/POP
 }
 
+// Register pairs that can be used to address memory when loading to/from A.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum IndirectTarget {
+    BC,
+    DE,
+    HLInc, // (HL+), HL is incremented after the access
+    HLDec, // (HL-), HL is decremented after the access
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Condition {
+    NZ,
+    Z,
+    NC,
+    C,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Instruction {
     /* 8-bit Load instructions */
     LDrr(ArithmeticTarget8, ArithmeticTarget8), // LD r,r'
     LDri(ArithmeticTarget8, u8),                // LD r,i
+    LDAind(IndirectTarget),                     // LD A,(rr)
+    LDindA(IndirectTarget),                     // LD (rr),A
+    LDAnn(u16),                                 // LD A,(nn)
+    LDnnA(u16),                                 // LD (nn),A
+    LDHAn(u8),                                  // LDH A,(n)
+    LDHnA(u8),                                  // LDH (n),A
+    LDHAC,                                      // LD A,(C)
+    LDHCA,                                      // LD (C),A
 
     /* 8-bit Arithmetic/Logic instructions */
     ADDr(ArithmeticTarget8), // add A,r
@@ -41,12 +69,14 @@ pub enum Instruction {
     CPi(u8),                 // cp  A,i
     INCr(ArithmeticTarget8), // inc r
     DECr(ArithmeticTarget8), // dec r
+    DAA,                     // daa
     CPL,                     // cpl
 
     /* 16-bit Arithmetic/Logic instructions */
     ADDHLRR(ArithmeticTarget16), // add HL, rr
     INCRR(ArithmeticTarget16),   // inc rr
     DECRR(ArithmeticTarget16),   // dec rr
+    ADDSPe(i8),                  // add SP, e
 
     /* Rotate and Shift instructions */
     RLCA,                     // rotate A left
@@ -69,13 +99,31 @@ pub enum Instruction {
 
     /* 16-bit load instructions */
     LDrrnn(ArithmeticTarget16, u16), // LD rr,nn
+    LDnnSP(u16),                     // LD (nn),SP
     LDSPHL(),                        // LD SP, HL
+    LDHLSPe(i8),                     // LD HL, SP+e
     PUSH(ArithmeticTarget16),        // PUSH rr
     POP(ArithmeticTarget16),         // POP rr
 
+    /* Jump instructions */
+    JPnn(u16),                // jp nn
+    JPccnn(Condition, u16),   // jp cc,nn
+    JPHL,                     // jp HL
+    JRe(i8),                  // jr e
+    JRcce(Condition, i8),     // jr cc,e
+    CALLnn(u16),              // call nn
+    CALLccnn(Condition, u16), // call cc,nn
+    RET,                      // ret
+    RETcc(Condition),         // ret cc
+    RETI,                     // reti
+    RSTn(u8),                 // rst n
+
     /* CPU Control instructions */
     SCF,  // scf
     CCF,  // ccf
     NOP,  // nop
     HALT, // halt
+    STOP, // stop
+    DI,   // di
+    EI,   // ei
 }
@@ -60,10 +60,6 @@ impl Registers {
     }
 
     // Other
-    pub fn set_flags(&mut self, value: u8, overflow: bool) {
-        self.f.set(value, overflow, self.a);
-    }
-
     pub fn set_carry_flag(&mut self) {
         self.f.set_carry_flag();
     }
@@ -83,22 +79,23 @@ const HALF_CARRY_FLAG_BYTE_POSITION: u8 = 5;
 const CARRY_FLAG_BYTE_POSITION: u8 = 4;
 
 impl FlagsRegister {
-    pub fn set(&mut self, value: u8, overflow: bool, a: u8) {
-        self.zero = value == 0;
-        self.subtract = false;
-        self.carry = overflow;
-        self.half_carry = (a & 0xF) + (value & 0xF) > 0xF;
+    pub fn set(&mut self, zero: bool, subtract: bool, half_carry: bool, carry: bool) {
+        self.zero = zero;
+        self.subtract = subtract;
+        self.half_carry = half_carry;
+        self.carry = carry;
     }
 
+    // SCF and CCF leave Z alone
     pub fn set_carry_flag(&mut self) {
-        self.zero = true;
         self.subtract = false;
+        self.half_carry = false;
         self.carry = true;
     }
 
     pub fn complement_carry_flag(&mut self) {
-        self.zero = true;
         self.subtract = false;
+        self.half_carry = false;
         self.carry = !self.carry;
     }
 
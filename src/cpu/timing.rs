@@ -0,0 +1,128 @@
+use crate::cpu::instructions::{ArithmeticTarget8, Instruction};
+
+/// Number of machine cycles (M-cycles, 4 clock ticks each) `instruction` takes.
+/// Conditional jumps, calls and returns are shorter when the branch isn't taken.
+pub fn cycles(instruction: &Instruction, branch_taken: bool) -> u8 {
+    base_cycles(instruction, branch_taken) + memory_operand_cycles(instruction)
+}
+
+// Every (HL) operand costs one extra cycle for the read and, for
+// read-modify-write instructions, another for the write back.
+fn memory_operand_cycles(instruction: &Instruction) -> u8 {
+    let (target, writes_back) = match *instruction {
+        Instruction::ADDr(reg)
+        | Instruction::ADCr(reg)
+        | Instruction::SUBr(reg)
+        | Instruction::SBCr(reg)
+        | Instruction::ANDr(reg)
+        | Instruction::XORr(reg)
+        | Instruction::ORr(reg)
+        | Instruction::CPr(reg)
+        | Instruction::BITnr(_, reg) => (reg, false),
+        Instruction::INCr(reg)
+        | Instruction::DECr(reg)
+        | Instruction::RLCr(reg)
+        | Instruction::RRCr(reg)
+        | Instruction::RLr(reg)
+        | Instruction::RRr(reg)
+        | Instruction::SLAr(reg)
+        | Instruction::SRAr(reg)
+        | Instruction::SRLr(reg)
+        | Instruction::SWAPr(reg)
+        | Instruction::SETnr(_, reg)
+        | Instruction::RESnr(_, reg) => (reg, true),
+        _ => return 0,
+    };
+
+    match (target, writes_back) {
+        (ArithmeticTarget8::HLI, true) => 2,
+        (ArithmeticTarget8::HLI, false) => 1,
+        _ => 0,
+    }
+}
+
+fn base_cycles(instruction: &Instruction, branch_taken: bool) -> u8 {
+    match instruction {
+        /* 8-bit Load instructions */
+        Instruction::LDrr(ArithmeticTarget8::HLI, _)
+        | Instruction::LDrr(_, ArithmeticTarget8::HLI) => 2,
+        Instruction::LDrr(_, _) => 1,
+        Instruction::LDri(ArithmeticTarget8::HLI, _) => 3,
+        Instruction::LDri(_, _) => 2,
+        Instruction::LDAind(_) | Instruction::LDindA(_) => 2,
+        Instruction::LDAnn(_) | Instruction::LDnnA(_) => 4,
+        Instruction::LDHAn(_) | Instruction::LDHnA(_) => 3,
+        Instruction::LDHAC | Instruction::LDHCA => 2,
+
+        /* 8-bit Arithmetic/Logic instructions */
+        Instruction::ADDr(_)
+        | Instruction::ADCr(_)
+        | Instruction::SUBr(_)
+        | Instruction::SBCr(_)
+        | Instruction::ANDr(_)
+        | Instruction::XORr(_)
+        | Instruction::ORr(_)
+        | Instruction::CPr(_) => 1,
+        Instruction::ADDi(_)
+        | Instruction::ADCi(_)
+        | Instruction::SUBi(_)
+        | Instruction::SBCi(_)
+        | Instruction::ANDi(_)
+        | Instruction::XORi(_)
+        | Instruction::ORi(_)
+        | Instruction::CPi(_) => 2,
+        Instruction::INCr(_) | Instruction::DECr(_) => 1,
+        Instruction::DAA | Instruction::CPL => 1,
+
+        /* 16-bit Arithmetic/Logic instructions */
+        Instruction::ADDHLRR(_) | Instruction::INCRR(_) | Instruction::DECRR(_) => 2,
+        Instruction::ADDSPe(_) => 4,
+
+        /* Rotate and Shift instructions */
+        Instruction::RLCA | Instruction::RLA | Instruction::RRCA | Instruction::RRA => 1,
+        Instruction::RLCr(_)
+        | Instruction::RRCr(_)
+        | Instruction::RLr(_)
+        | Instruction::RRr(_)
+        | Instruction::SLAr(_)
+        | Instruction::SRAr(_)
+        | Instruction::SRLr(_)
+        | Instruction::SWAPr(_) => 2,
+
+        /* Single bit operations */
+        Instruction::BITnr(_, _) | Instruction::SETnr(_, _) | Instruction::RESnr(_, _) => 2,
+
+        /* 16-bit load instructions */
+        Instruction::LDrrnn(_, _) => 3,
+        Instruction::LDnnSP(_) => 5,
+        Instruction::LDSPHL() => 2,
+        Instruction::LDHLSPe(_) => 3,
+        Instruction::PUSH(_) => 4,
+        Instruction::POP(_) => 3,
+
+        /* Jump instructions */
+        Instruction::JPnn(_) => 4,
+        Instruction::JPccnn(_, _) if branch_taken => 4,
+        Instruction::JPccnn(_, _) => 3,
+        Instruction::JPHL => 1,
+        Instruction::JRe(_) => 3,
+        Instruction::JRcce(_, _) if branch_taken => 3,
+        Instruction::JRcce(_, _) => 2,
+        Instruction::CALLnn(_) => 6,
+        Instruction::CALLccnn(_, _) if branch_taken => 6,
+        Instruction::CALLccnn(_, _) => 3,
+        Instruction::RET | Instruction::RETI => 4,
+        Instruction::RETcc(_) if branch_taken => 5,
+        Instruction::RETcc(_) => 2,
+        Instruction::RSTn(_) => 4,
+
+        /* CPU Control instructions */
+        Instruction::SCF
+        | Instruction::CCF
+        | Instruction::NOP
+        | Instruction::HALT
+        | Instruction::STOP
+        | Instruction::DI
+        | Instruction::EI => 1,
+    }
+}
@@ -0,0 +1,43 @@
+pub const INTERRUPT_FLAG_ADDRESS: u16 = 0xFF0F;
+pub const INTERRUPT_ENABLE_ADDRESS: u16 = 0xFFFF;
+
+// Only the low five bits of IE and IF correspond to interrupt sources.
+pub const INTERRUPT_MASK: u8 = 0x1F;
+
+/// Interrupt sources, in priority order (VBlank is serviced first).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Interrupt {
+    VBlank,
+    LcdStat,
+    Timer,
+    Serial,
+    Joypad,
+}
+
+const PRIORITY: [Interrupt; 5] = [
+    Interrupt::VBlank,
+    Interrupt::LcdStat,
+    Interrupt::Timer,
+    Interrupt::Serial,
+    Interrupt::Joypad,
+];
+
+impl Interrupt {
+    /// The bit this interrupt occupies in IE and IF.
+    pub fn mask(self) -> u8 {
+        1 << self as u8
+    }
+
+    /// Address the CPU jumps to when servicing this interrupt.
+    pub fn vector(self) -> u16 {
+        0x40 + 8 * self as u16
+    }
+
+    /// The highest priority interrupt set in `pending` (typically IE & IF).
+    pub fn highest_priority(pending: u8) -> Option<Interrupt> {
+        PRIORITY
+            .iter()
+            .copied()
+            .find(|interrupt| pending & interrupt.mask() != 0)
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::VecDeque;
+use std::ops::BitOr;
+
+use crate::interrupts::Interrupt;
+
+// For scheduling input by frame number.
+pub use crate::ppu::M_CYCLES_PER_FRAME;
+
+pub const P1_ADDRESS: u16 = 0xFF00;
+
+// Writing 0 to bit 4 selects the direction keys, bit 5 the action buttons.
+pub const P1_SELECT_DIRECTIONS: u8 = 0x10;
+pub const P1_SELECT_ACTIONS: u8 = 0x20;
+const P1_SELECT: u8 = P1_SELECT_DIRECTIONS | P1_SELECT_ACTIONS;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Button {
+    Right,
+    Left,
+    Up,
+    Down,
+    A,
+    B,
+    Select,
+    Start,
+}
+
+impl Button {
+    // Directions go in the low nibble and actions in the high one, each in
+    // the order of the P1 bits they drive.
+    fn mask(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// A set of pressed buttons.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Buttons(u8);
+
+impl Buttons {
+    pub const NONE: Buttons = Buttons(0);
+
+    pub fn contains(self, button: Button) -> bool {
+        self.0 & button.mask() != 0
+    }
+
+    fn directions(self) -> u8 {
+        self.0 & 0x0F
+    }
+
+    fn actions(self) -> u8 {
+        self.0 >> 4
+    }
+}
+
+impl From<Button> for Buttons {
+    fn from(button: Button) -> Self {
+        Buttons(button.mask())
+    }
+}
+
+impl FromIterator<Button> for Buttons {
+    fn from_iter<I: IntoIterator<Item = Button>>(buttons: I) -> Self {
+        buttons
+            .into_iter()
+            .fold(Buttons::NONE, |set, button| set | button)
+    }
+}
+
+impl<T: Into<Buttons>> BitOr<T> for Buttons {
+    type Output = Buttons;
+
+    fn bitor(self, other: T) -> Buttons {
+        Buttons(self.0 | other.into().0)
+    }
+}
+
+impl BitOr for Button {
+    type Output = Buttons;
+
+    fn bitor(self, other: Button) -> Buttons {
+        Buttons::from(self) | other
+    }
+}
+
+/// The joypad and P1. Buttons are wired in a 2x4 matrix: the game pulls one
+/// or both select lines low and reads the four input lines, where a pressed
+/// button reads as 0. The interrupt is requested whenever an input line goes
+/// from high to low, which also wakes the CPU from STOP.
+///
+/// Input can be set immediately with `set_pressed`, or queued with `schedule`
+/// against the joypad's M-cycle count so scripted input lands on exactly the
+/// same cycle every run.
+pub struct Joypad {
+    select: u8,
+    pressed: Buttons,
+    // Input lines as of the last tick, to find high-to-low transitions.
+    lines: u8,
+    cycle: u64,
+    // Pending input changes in cycle order.
+    schedule: VecDeque<(u64, Buttons)>,
+}
+
+impl Joypad {
+    pub fn new() -> Joypad {
+        Joypad {
+            select: P1_SELECT,
+            pressed: Buttons::NONE,
+            lines: 0x0F,
+            cycle: 0,
+            schedule: VecDeque::new(),
+        }
+    }
+
+    /// M-cycles ticked since power on.
+    pub fn cycle(&self) -> u64 {
+        self.cycle
+    }
+
+    pub fn pressed(&self) -> Buttons {
+        self.pressed
+    }
+
+    /// Replaces the set of pressed buttons. The interrupt, if any, is
+    /// requested on the next tick.
+    pub fn set_pressed(&mut self, buttons: impl Into<Buttons>) {
+        self.pressed = buttons.into();
+    }
+
+    /// Makes `buttons` the set of pressed buttons from M-cycle `cycle` on.
+    /// Changes scheduled for the same cycle apply in the order they were made,
+    /// and ones in the past apply on the next tick.
+    pub fn schedule(&mut self, cycle: u64, buttons: impl Into<Buttons>) {
+        let index = self.schedule.partition_point(|(at, _)| *at <= cycle);
+        self.schedule.insert(index, (cycle, buttons.into()));
+    }
+
+    pub fn read(&self) -> u8 {
+        0xC0 | self.select | self.input_lines()
+    }
+
+    pub fn write(&mut self, value: u8) {
+        self.select = value & P1_SELECT;
+    }
+
+    /// Advances by one M-cycle, returning the interrupts raised (as IF bits).
+    pub fn tick(&mut self) -> u8 {
+        self.cycle += 1;
+        while let Some(&(at, buttons)) = self.schedule.front() {
+            if at > self.cycle {
+                break;
+            }
+            self.pressed = buttons;
+            self.schedule.pop_front();
+        }
+
+        let lines = self.input_lines();
+        let falling = self.lines & !lines;
+        self.lines = lines;
+        if falling != 0 {
+            Interrupt::Joypad.mask()
+        } else {
+            0
+        }
+    }
+
+    fn input_lines(&self) -> u8 {
+        let mut low = 0;
+        if self.select & P1_SELECT_DIRECTIONS == 0 {
+            low |= self.pressed.directions();
+        }
+        if self.select & P1_SELECT_ACTIONS == 0 {
+            low |= self.pressed.actions();
+        }
+        !low & 0x0F
+    }
+}
+
+impl Default for Joypad {
+    fn default() -> Self {
+        Joypad::new()
+    }
+}
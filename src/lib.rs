@@ -1 +1,8 @@
+pub mod bus;
+pub mod cartridge;
 pub mod cpu;
+pub mod interrupts;
+pub mod joypad;
+pub mod ppu;
+pub mod serial;
+pub mod timer;
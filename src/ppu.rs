@@ -0,0 +1,458 @@
+mod fifo;
+mod scanline;
+mod sprite;
+
+pub use fifo::FifoRenderer;
+pub use scanline::ScanlineRenderer;
+pub use sprite::{
+    Sprite, SPRITES_PER_LINE, SPRITE_BEHIND_BG, SPRITE_PALETTE, SPRITE_X_FLIP, SPRITE_Y_FLIP,
+};
+
+use crate::interrupts::Interrupt;
+
+pub const SCREEN_WIDTH: usize = 160;
+pub const SCREEN_HEIGHT: usize = 144;
+
+pub const VRAM_SIZE: usize = 0x2000;
+pub const OAM_SIZE: usize = 0xA0;
+
+pub const LCDC_ADDRESS: u16 = 0xFF40;
+pub const STAT_ADDRESS: u16 = 0xFF41;
+pub const SCY_ADDRESS: u16 = 0xFF42;
+pub const SCX_ADDRESS: u16 = 0xFF43;
+pub const LY_ADDRESS: u16 = 0xFF44;
+pub const LYC_ADDRESS: u16 = 0xFF45;
+pub const BGP_ADDRESS: u16 = 0xFF47;
+pub const OBP0_ADDRESS: u16 = 0xFF48;
+pub const OBP1_ADDRESS: u16 = 0xFF49;
+pub const WY_ADDRESS: u16 = 0xFF4A;
+pub const WX_ADDRESS: u16 = 0xFF4B;
+
+// LCDC bits
+pub const LCDC_ENABLE: u8 = 0x80;
+pub const LCDC_WINDOW_MAP: u8 = 0x40;
+pub const LCDC_WINDOW_ENABLE: u8 = 0x20;
+pub const LCDC_TILE_DATA: u8 = 0x10;
+pub const LCDC_BG_MAP: u8 = 0x08;
+pub const LCDC_OBJ_SIZE: u8 = 0x04;
+pub const LCDC_OBJ_ENABLE: u8 = 0x02;
+pub const LCDC_BG_ENABLE: u8 = 0x01;
+
+// Tile maps are 32x32 tile indices at 9800 or 9C00.
+const TILE_MAP_LOW: usize = 0x1800;
+const TILE_MAP_HIGH: usize = 0x1C00;
+
+// STAT bits
+pub const STAT_LYC_INTERRUPT: u8 = 0x40;
+pub const STAT_OAM_INTERRUPT: u8 = 0x20;
+pub const STAT_VBLANK_INTERRUPT: u8 = 0x10;
+pub const STAT_HBLANK_INTERRUPT: u8 = 0x08;
+pub const STAT_LYC_EQUAL: u8 = 0x04;
+const STAT_WRITABLE: u8 = 0x78;
+
+pub const DOTS_PER_LINE: u16 = 456;
+pub const LINES_PER_FRAME: u8 = 154;
+/// M-cycles in one frame, at 4 dots each.
+pub const M_CYCLES_PER_FRAME: u64 = LINES_PER_FRAME as u64 * DOTS_PER_LINE as u64 / 4;
+pub const OAM_SCAN_DOTS: u16 = 80;
+pub const PIXEL_TRANSFER_DOTS: u16 = 172;
+
+/// The value of STAT's low two bits in each mode.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Mode {
+    HBlank = 0,
+    VBlank = 1,
+    OamScan = 2,
+    PixelTransfer = 3,
+}
+
+/// VRAM, OAM and the LCD registers: everything a `Renderer` reads.
+pub struct PpuState {
+    pub vram: Box<[u8; VRAM_SIZE]>,
+    pub oam: [u8; OAM_SIZE],
+    pub lcdc: u8,
+    pub stat: u8, // only the interrupt enable bits; mode and LYC=LY are computed
+    pub scy: u8,
+    pub scx: u8,
+    pub ly: u8,
+    pub lyc: u8,
+    pub bgp: u8,
+    pub obp0: u8,
+    pub obp1: u8,
+    pub wy: u8,
+    pub wx: u8,
+    /// The objects OAM scan found on the current line, in OAM order.
+    pub sprites: Vec<Sprite>,
+}
+
+impl PpuState {
+    /// The colour (0-3, before the palette) of pixel `x`, `y` of the tile
+    /// numbered `index`, honouring LCDC's choice of tile data area: 8000 with
+    /// unsigned indices, or 9000 with signed ones.
+    pub fn tile_pixel(&self, index: u8, x: u8, y: u8) -> u8 {
+        let (low, high) = self.tile_row(index, y);
+        let bit = 7 - (x & 7);
+        ((high >> bit) & 1) << 1 | (low >> bit) & 1
+    }
+
+    /// The two bytes of row `y` of background/window tile `index`.
+    pub fn tile_row(&self, index: u8, y: u8) -> (u8, u8) {
+        let tile = if self.lcdc & LCDC_TILE_DATA != 0 {
+            index as usize * 16
+        } else {
+            (0x1000 + (index as i8 as i32) * 16) as usize
+        };
+        let row = tile + (y as usize & 7) * 2;
+        (self.vram[row], self.vram[row + 1])
+    }
+
+    /// The tile index at tile column `x`, row `y` of the background map.
+    pub fn bg_tile(&self, x: u8, y: u8) -> u8 {
+        let map = if self.lcdc & LCDC_BG_MAP != 0 {
+            TILE_MAP_HIGH
+        } else {
+            TILE_MAP_LOW
+        };
+        self.vram[map + (y as usize & 31) * 32 + (x as usize & 31)]
+    }
+
+    /// The tile index at tile column `x`, row `y` of the window map.
+    pub fn window_tile(&self, x: u8, y: u8) -> u8 {
+        let map = if self.lcdc & LCDC_WINDOW_MAP != 0 {
+            TILE_MAP_HIGH
+        } else {
+            TILE_MAP_LOW
+        };
+        self.vram[map + (y as usize & 31) * 32 + (x as usize & 31)]
+    }
+
+    /// Applies BGP to a background colour.
+    pub fn bg_shade(&self, colour: u8) -> u8 {
+        (self.bgp >> (colour * 2)) & 0x03
+    }
+}
+
+/// Draws the pixels of a line during pixel transfer. The PPU handles the mode
+/// state machine and hands the renderer every dot of mode 3; how long mode 3
+/// lasts is up to the renderer.
+pub trait Renderer {
+    /// Line 0 of a new frame is about to start.
+    fn start_frame(&mut self);
+
+    /// Pixel transfer is starting on line `state.ly`.
+    fn start_line(&mut self, state: &PpuState);
+
+    /// One dot of pixel transfer. Shades go into `line`; returns true once
+    /// all of it has been drawn, which ends mode 3.
+    fn tick(&mut self, state: &PpuState, line: &mut [u8]) -> bool;
+}
+
+/// The picture processing unit. It owns VRAM, OAM and the LCD registers and
+/// is stepped one dot (4 MHz clock) at a time. Every visible line goes through
+/// OAM scan (80 dots), pixel transfer (172 dots or more, depending on the
+/// renderer) and HBlank (the rest of the 456), followed by 10 lines of VBlank.
+///
+/// OAM scan looks at one entry every two dots and keeps the first ten objects
+/// that are on the line, whatever their X position.
+///
+/// The framebuffer holds one shade (0 = white to 3 = black) per pixel. Lines
+/// are drawn into it as they're rendered, so it holds a complete frame once
+/// VBlank starts.
+///
+/// The four STAT interrupt sources share one line and an interrupt is only
+/// requested when it goes from low to high, so e.g. HBlank followed by OAM
+/// scan only interrupts once. The CPU can't see OAM during modes 2 and 3 or
+/// VRAM during mode 3; `read` and `write` apply that, while `state` gives
+/// unrestricted access.
+pub struct Ppu {
+    pub state: PpuState,
+    renderer: Box<dyn Renderer>,
+    // A renderer waiting for the next frame to take over.
+    next_renderer: Option<Box<dyn Renderer>>,
+    mode: Mode,
+    dot: u16,
+    // The line being drawn. Differs from LY on line 153, where LY reads 0
+    // after the first few dots.
+    line: u8,
+    stat_line: bool,
+    // Line 0 after turning the LCD on skips OAM scan, and the whole frame is
+    // left blank.
+    first_line: bool,
+    blank_frame: bool,
+    interrupts: u8,
+    framebuffer: Box<[u8; SCREEN_WIDTH * SCREEN_HEIGHT]>,
+    frames: u64,
+}
+
+impl Ppu {
+    /// A PPU in the state the boot ROM leaves it in, drawing whole lines at
+    /// a time with the `ScanlineRenderer`.
+    pub fn new() -> Ppu {
+        Ppu::with_renderer(Box::new(ScanlineRenderer::new()))
+    }
+
+    pub fn with_renderer(mut renderer: Box<dyn Renderer>) -> Ppu {
+        renderer.start_frame();
+        Ppu {
+            state: PpuState {
+                vram: Box::new([0; VRAM_SIZE]),
+                oam: [0; OAM_SIZE],
+                lcdc: 0x91,
+                stat: 0,
+                scy: 0,
+                scx: 0,
+                ly: 0,
+                lyc: 0,
+                bgp: 0xFC,
+                obp0: 0xFF,
+                obp1: 0xFF,
+                wy: 0,
+                wx: 0,
+                sprites: Vec::with_capacity(SPRITES_PER_LINE),
+            },
+            renderer,
+            next_renderer: None,
+            mode: Mode::OamScan,
+            dot: 0,
+            line: 0,
+            stat_line: false,
+            first_line: false,
+            blank_frame: false,
+            interrupts: 0,
+            framebuffer: Box::new([0; SCREEN_WIDTH * SCREEN_HEIGHT]),
+            frames: 0,
+        }
+    }
+
+    /// Swaps the renderer. Takes effect from the next frame, so the current
+    /// one finishes whatever it has started.
+    pub fn set_renderer(&mut self, renderer: Box<dyn Renderer>) {
+        self.next_renderer = Some(renderer);
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn ly(&self) -> u8 {
+        self.state.ly
+    }
+
+    /// The dot within the current line, 0-455.
+    pub fn dot(&self) -> u16 {
+        self.dot
+    }
+
+    pub fn framebuffer(&self) -> &[u8; SCREEN_WIDTH * SCREEN_HEIGHT] {
+        &self.framebuffer
+    }
+
+    /// How many frames have been completed, counted at the start of VBlank.
+    pub fn frame_count(&self) -> u64 {
+        self.frames
+    }
+
+    pub fn lcd_enabled(&self) -> bool {
+        self.state.lcdc & LCDC_ENABLE != 0
+    }
+
+    /// Whether the CPU can access VRAM right now.
+    pub fn vram_accessible(&self) -> bool {
+        !self.lcd_enabled() || self.mode != Mode::PixelTransfer
+    }
+
+    /// Whether the CPU can access OAM right now.
+    pub fn oam_accessible(&self) -> bool {
+        !self.lcd_enabled() || !matches!(self.mode, Mode::OamScan | Mode::PixelTransfer)
+    }
+
+    /// Reads VRAM (8000-9FFF), OAM (FE00-FE9F) or an LCD register as the CPU
+    /// sees them. Locked VRAM and OAM read as 0xFF.
+    pub fn read(&self, address: u16) -> u8 {
+        match address {
+            0x8000..=0x9FFF if !self.vram_accessible() => 0xFF,
+            0x8000..=0x9FFF => self.state.vram[address as usize - 0x8000],
+            0xFE00..=0xFE9F if !self.oam_accessible() => 0xFF,
+            0xFE00..=0xFE9F => self.state.oam[address as usize - 0xFE00],
+            LCDC_ADDRESS => self.state.lcdc,
+            STAT_ADDRESS => self.read_stat(),
+            SCY_ADDRESS => self.state.scy,
+            SCX_ADDRESS => self.state.scx,
+            LY_ADDRESS => self.state.ly,
+            LYC_ADDRESS => self.state.lyc,
+            BGP_ADDRESS => self.state.bgp,
+            OBP0_ADDRESS => self.state.obp0,
+            OBP1_ADDRESS => self.state.obp1,
+            WY_ADDRESS => self.state.wy,
+            WX_ADDRESS => self.state.wx,
+            _ => 0xFF,
+        }
+    }
+
+    /// Writes as the CPU does; writes to locked VRAM and OAM are dropped.
+    pub fn write(&mut self, address: u16, value: u8) {
+        match address {
+            0x8000..=0x9FFF if !self.vram_accessible() => {}
+            0x8000..=0x9FFF => self.state.vram[address as usize - 0x8000] = value,
+            0xFE00..=0xFE9F if !self.oam_accessible() => {}
+            0xFE00..=0xFE9F => self.state.oam[address as usize - 0xFE00] = value,
+            LCDC_ADDRESS => self.write_lcdc(value),
+            STAT_ADDRESS => self.write_stat(value),
+            SCY_ADDRESS => self.state.scy = value,
+            SCX_ADDRESS => self.state.scx = value,
+            LY_ADDRESS => {} // read only
+            LYC_ADDRESS => {
+                self.state.lyc = value;
+                self.update_stat_line();
+            }
+            BGP_ADDRESS => self.state.bgp = value,
+            OBP0_ADDRESS => self.state.obp0 = value,
+            OBP1_ADDRESS => self.state.obp1 = value,
+            WY_ADDRESS => self.state.wy = value,
+            WX_ADDRESS => self.state.wx = value,
+            _ => {}
+        }
+    }
+
+    // Bit 7 is unused and reads as 1. With the LCD off the mode reads as 0.
+    fn read_stat(&self) -> u8 {
+        let mut stat = 0x80 | self.state.stat;
+        if self.lcd_enabled() {
+            stat |= self.mode as u8;
+            if self.state.ly == self.state.lyc {
+                stat |= STAT_LYC_EQUAL;
+            }
+        }
+        stat
+    }
+
+    // On the DMG, writing STAT enables the HBlank, VBlank and LYC sources for
+    // a cycle before the written value takes effect, so a write during HBlank,
+    // VBlank or while LY == LYC requests an interrupt if the line was low.
+    fn write_stat(&mut self, value: u8) {
+        let bug_sources = STAT_LYC_INTERRUPT | STAT_VBLANK_INTERRUPT | STAT_HBLANK_INTERRUPT;
+        if !self.stat_line && self.stat_sources(bug_sources) {
+            self.interrupts |= Interrupt::LcdStat.mask();
+        }
+        self.state.stat = value & STAT_WRITABLE;
+        self.stat_line = self.stat_sources(self.state.stat);
+    }
+
+    // Turning the LCD off resets it to the top of the screen and blanks it.
+    // When it's turned back on, line 0 starts without an OAM scan and the
+    // first frame isn't shown.
+    fn write_lcdc(&mut self, value: u8) {
+        let was_enabled = self.lcd_enabled();
+        self.state.lcdc = value;
+        if was_enabled && !self.lcd_enabled() {
+            self.state.ly = 0;
+            self.line = 0;
+            self.dot = 0;
+            self.mode = Mode::HBlank;
+            self.stat_line = false;
+            self.framebuffer.fill(0);
+        } else if !was_enabled && self.lcd_enabled() {
+            self.first_line = true;
+            self.blank_frame = true;
+            self.state.sprites.clear();
+            self.start_frame();
+            self.update_stat_line();
+        }
+    }
+
+    /// Advances by one dot, returning the interrupts raised (as IF bits).
+    pub fn tick(&mut self) -> u8 {
+        if !self.lcd_enabled() {
+            return std::mem::take(&mut self.interrupts);
+        }
+
+        self.dot += 1;
+        if self.dot == DOTS_PER_LINE {
+            self.dot = 0;
+            self.line += 1;
+            if self.line == LINES_PER_FRAME {
+                self.line = 0;
+                self.start_frame();
+            }
+            self.state.ly = self.line;
+            if self.line == SCREEN_HEIGHT as u8 {
+                self.mode = Mode::VBlank;
+                self.interrupts |= Interrupt::VBlank.mask();
+                self.frames += 1;
+                self.blank_frame = false;
+            } else if self.line < SCREEN_HEIGHT as u8 {
+                self.mode = Mode::OamScan;
+                self.state.sprites.clear();
+            }
+        } else if self.line == LINES_PER_FRAME - 1 && self.dot == 4 {
+            // LY wraps early and reads 0 for most of line 153
+            self.state.ly = 0;
+        } else if self.mode == Mode::OamScan && self.dot < OAM_SCAN_DOTS {
+            if self.dot % 2 == 1 {
+                self.scan_oam_entry(self.dot as usize / 2);
+            }
+        } else if self.dot == OAM_SCAN_DOTS && (self.mode == Mode::OamScan || self.first_line) {
+            self.first_line = false;
+            self.mode = Mode::PixelTransfer;
+            self.renderer.start_line(&self.state);
+        } else if self.mode == Mode::PixelTransfer {
+            let start = self.line as usize * SCREEN_WIDTH;
+            let line = &mut self.framebuffer[start..start + SCREEN_WIDTH];
+            if self.renderer.tick(&self.state, line) {
+                if self.blank_frame {
+                    line.fill(0);
+                }
+                self.mode = Mode::HBlank;
+            }
+        }
+        self.update_stat_line();
+        std::mem::take(&mut self.interrupts)
+    }
+
+    fn start_frame(&mut self) {
+        if let Some(renderer) = self.next_renderer.take() {
+            self.renderer = renderer;
+        }
+        self.renderer.start_frame();
+    }
+
+    fn scan_oam_entry(&mut self, index: usize) {
+        let state = &mut self.state;
+        if state.sprites.len() == SPRITES_PER_LINE {
+            return;
+        }
+        let sprite = Sprite::from_oam(&state.oam[index * 4..index * 4 + 4]);
+        if sprite.on_line(state.ly, state.sprite_height()) {
+            state.sprites.push(sprite);
+        }
+    }
+
+    // Whether any of the sources enabled in `enables` is active.
+    fn stat_sources(&self, enables: u8) -> bool {
+        if !self.lcd_enabled() {
+            return false;
+        }
+        let mode = match self.mode {
+            Mode::HBlank => STAT_HBLANK_INTERRUPT,
+            Mode::VBlank => STAT_VBLANK_INTERRUPT,
+            Mode::OamScan => STAT_OAM_INTERRUPT,
+            Mode::PixelTransfer => 0,
+        };
+        enables & mode != 0
+            || (enables & STAT_LYC_INTERRUPT != 0 && self.state.ly == self.state.lyc)
+    }
+
+    fn update_stat_line(&mut self) {
+        let line = self.stat_sources(self.state.stat);
+        if line && !self.stat_line {
+            self.interrupts |= Interrupt::LcdStat.mask();
+        }
+        self.stat_line = line;
+    }
+}
+
+impl Default for Ppu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::VecDeque;
+
+use crate::ppu::{
+    PpuState, Renderer, Sprite, LCDC_BG_ENABLE, LCDC_OBJ_ENABLE, LCDC_WINDOW_ENABLE, SCREEN_WIDTH,
+    SPRITES_PER_LINE,
+};
+
+// Dots spent on the fetch that starts every line and is thrown away.
+const STARTUP_DOTS: u8 = 6;
+// Dots an object fetch takes once the background fetcher is out of the way.
+const SPRITE_FETCH_DOTS: u8 = 6;
+
+#[derive(Clone, Copy)]
+struct SpritePixel {
+    colour: u8, // 0 is transparent
+    sprite: Sprite,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FetchStep {
+    Tile,
+    DataLow,
+    DataHigh,
+    Push,
+}
+
+/// Fetches background or window tiles eight pixels at a time. Each of the
+/// first three steps takes two dots; pushing waits for the FIFO to empty.
+struct Fetcher {
+    step: FetchStep,
+    dots: u8,
+    tile_x: u8, // tiles fetched so far on this line (or in the window)
+    tile: u8,
+    low: u8,
+    high: u8,
+}
+
+impl Fetcher {
+    fn new() -> Fetcher {
+        Fetcher {
+            step: FetchStep::Tile,
+            dots: 0,
+            tile_x: 0,
+            tile: 0,
+            low: 0,
+            high: 0,
+        }
+    }
+}
+
+/// Models the DMG's pixel FIFO: a tile fetcher feeds a FIFO that shifts out
+/// one pixel per dot. Mode 3 lasts as long as that takes, so fine scrolling
+/// and the window lengthen it, and registers are read at the moment the
+/// hardware would read them, so mid-line writes show up mid-line.
+///
+/// When the next pixel out is the left edge of an object, everything stalls
+/// while it's fetched into a second FIFO that's mixed with the first. That
+/// takes 6 dots, plus up to 5 more waiting for the background fetcher when
+/// it's the first object on that background tile.
+pub struct FifoRenderer {
+    fifo: VecDeque<u8>, // background colours, before the palette
+    sprite_fifo: VecDeque<SpritePixel>,
+    fetcher: Fetcher,
+    startup: u8,
+    stall: u8, // dots left of an object fetch
+    sprites_fetched: [bool; SPRITES_PER_LINE],
+    penalised_tiles: u32, // background tiles that have already made an object wait
+    x: u8,                // pixels drawn so far
+    discard: u8,          // pixels still to drop for SCX's fine scroll
+    window_active: bool,
+    window_y_reached: bool, // WY has matched LY at some point this frame
+    window_line: u8,
+}
+
+impl FifoRenderer {
+    pub fn new() -> FifoRenderer {
+        FifoRenderer {
+            fifo: VecDeque::with_capacity(16),
+            sprite_fifo: VecDeque::with_capacity(8),
+            fetcher: Fetcher::new(),
+            startup: STARTUP_DOTS,
+            stall: 0,
+            sprites_fetched: [false; SPRITES_PER_LINE],
+            penalised_tiles: 0,
+            x: 0,
+            discard: 0,
+            window_active: false,
+            window_y_reached: false,
+            window_line: 0,
+        }
+    }
+
+    fn fetch(&mut self, state: &PpuState) {
+        let fetcher = &mut self.fetcher;
+        if fetcher.step == FetchStep::Push {
+            if self.fifo.is_empty() {
+                for bit in (0..8).rev() {
+                    let colour = ((fetcher.high >> bit) & 1) << 1 | (fetcher.low >> bit) & 1;
+                    self.fifo.push_back(colour);
+                }
+                fetcher.tile_x += 1;
+                fetcher.step = FetchStep::Tile;
+            }
+            return;
+        }
+
+        fetcher.dots += 1;
+        if fetcher.dots < 2 {
+            return;
+        }
+        fetcher.dots = 0;
+
+        let y = if self.window_active {
+            self.window_line
+        } else {
+            state.scy.wrapping_add(state.ly)
+        };
+        fetcher.step = match fetcher.step {
+            FetchStep::Tile => {
+                fetcher.tile = if self.window_active {
+                    state.window_tile(fetcher.tile_x, y / 8)
+                } else {
+                    state.bg_tile((state.scx / 8).wrapping_add(fetcher.tile_x), y / 8)
+                };
+                FetchStep::DataLow
+            }
+            FetchStep::DataLow => {
+                fetcher.low = state.tile_row(fetcher.tile, y).0;
+                FetchStep::DataHigh
+            }
+            _ => {
+                fetcher.high = state.tile_row(fetcher.tile, y).1;
+                FetchStep::Push
+            }
+        };
+    }
+
+    // Loads the next object starting at the current pixel, if there is one,
+    // returning how long that stalls the FIFO for.
+    fn fetch_sprite(&mut self, state: &PpuState) -> Option<u8> {
+        if state.lcdc & LCDC_OBJ_ENABLE == 0 {
+            return None;
+        }
+        // several can be due at once at the left edge; lowest X goes first
+        let index = (0..state.sprites.len())
+            .filter(|&i| !self.sprites_fetched[i] && state.sprites[i].x <= self.x + 8)
+            .min_by_key(|&i| state.sprites[i].x)?;
+        self.sprites_fetched[index] = true;
+        let sprite = state.sprites[index];
+
+        // objects hanging off the left edge lose their first few pixels
+        let skip = (self.x + 8 - sprite.x) as usize;
+        while self.sprite_fifo.len() < 8 {
+            self.sprite_fifo
+                .push_back(SpritePixel { colour: 0, sprite });
+        }
+        let pixels = state.sprite_row(&sprite);
+        for (slot, colour) in self.sprite_fifo.iter_mut().zip(&pixels[skip.min(8)..]) {
+            // anything already there came from a higher priority object
+            if slot.colour == 0 {
+                *slot = SpritePixel {
+                    colour: *colour,
+                    sprite,
+                };
+            }
+        }
+
+        let position = sprite.x as u16 + (state.scx & 7) as u16;
+        let tile = position / 8;
+        let wait = if self.penalised_tiles & (1 << tile) == 0 {
+            self.penalised_tiles |= 1 << tile;
+            5 - (position % 8).min(5) as u8
+        } else {
+            0
+        };
+        Some(SPRITE_FETCH_DOTS + wait)
+    }
+
+    fn window_starts(&self, state: &PpuState) -> bool {
+        !self.window_active
+            && self.window_y_reached
+            && state.lcdc & LCDC_WINDOW_ENABLE != 0
+            && self.x as usize + 7 >= state.wx as usize
+    }
+}
+
+impl Default for FifoRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Renderer for FifoRenderer {
+    fn start_frame(&mut self) {
+        self.window_y_reached = false;
+        self.window_line = 0;
+    }
+
+    fn start_line(&mut self, state: &PpuState) {
+        if state.ly == state.wy {
+            self.window_y_reached = true;
+        }
+        self.fifo.clear();
+        self.sprite_fifo.clear();
+        self.fetcher = Fetcher::new();
+        self.startup = STARTUP_DOTS;
+        self.stall = 0;
+        self.sprites_fetched = [false; SPRITES_PER_LINE];
+        self.penalised_tiles = 0;
+        self.x = 0;
+        self.discard = state.scx & 7;
+        self.window_active = false;
+    }
+
+    fn tick(&mut self, state: &PpuState, line: &mut [u8]) -> bool {
+        if self.startup > 0 {
+            self.startup -= 1;
+            return false;
+        }
+        if self.stall > 0 {
+            self.stall -= 1;
+            return false;
+        }
+        if let Some(stall) = self.fetch_sprite(state) {
+            self.stall = stall - 1;
+            return false;
+        }
+
+        // reaching the window throws away what's been fetched and starts over
+        // from the window's first tile
+        if self.window_starts(state) {
+            self.window_active = true;
+            self.fifo.clear();
+            self.fetcher = Fetcher::new();
+        }
+
+        self.fetch(state);
+
+        if let Some(colour) = self.fifo.pop_front() {
+            if self.discard > 0 && !self.window_active {
+                self.discard -= 1;
+            } else {
+                let colour = if state.lcdc & LCDC_BG_ENABLE != 0 {
+                    colour
+                } else {
+                    0
+                };
+                line[self.x as usize] = match self.sprite_fifo.pop_front() {
+                    Some(pixel)
+                        if pixel.colour != 0
+                            && state.lcdc & LCDC_OBJ_ENABLE != 0
+                            && !(pixel.sprite.behind_bg() && colour != 0) =>
+                    {
+                        state.sprite_shade(&pixel.sprite, pixel.colour)
+                    }
+                    _ => state.bg_shade(colour),
+                };
+                self.x += 1;
+            }
+        }
+
+        if self.x as usize == SCREEN_WIDTH {
+            if self.window_active {
+                self.window_line += 1;
+            }
+            return true;
+        }
+        false
+    }
+}
@@ -0,0 +1,109 @@
+use crate::ppu::{
+    PpuState, Renderer, LCDC_BG_ENABLE, LCDC_OBJ_ENABLE, LCDC_WINDOW_ENABLE, PIXEL_TRANSFER_DOTS,
+    SCREEN_WIDTH,
+};
+
+/// Draws each line in one go at the end of a fixed length pixel transfer.
+/// Fast, but register writes during mode 3 affect the whole line.
+pub struct ScanlineRenderer {
+    dots: u16,
+    window_line: u8, // lines of the window drawn so far this frame
+    bg_colours: [u8; SCREEN_WIDTH],
+}
+
+impl ScanlineRenderer {
+    pub fn new() -> ScanlineRenderer {
+        ScanlineRenderer {
+            dots: 0,
+            window_line: 0,
+            bg_colours: [0; SCREEN_WIDTH],
+        }
+    }
+
+    // Fills in bg_colours, returning whether any of the window was on the line.
+    fn render_background(&mut self, state: &PpuState) -> bool {
+        // on DMG, clearing bit 0 blanks both background and window
+        if state.lcdc & LCDC_BG_ENABLE == 0 {
+            self.bg_colours.fill(0);
+            return false;
+        }
+
+        let window_visible =
+            state.lcdc & LCDC_WINDOW_ENABLE != 0 && state.ly >= state.wy && state.wx <= 166;
+        let mut window_drawn = false;
+        for (x, colour) in self.bg_colours.iter_mut().enumerate() {
+            // WX is the window's left edge plus 7
+            *colour = if window_visible && x + 7 >= state.wx as usize {
+                window_drawn = true;
+                let window_x = (x + 7 - state.wx as usize) as u8;
+                let index = state.window_tile(window_x / 8, self.window_line / 8);
+                state.tile_pixel(index, window_x, self.window_line)
+            } else {
+                let bg_x = state.scx.wrapping_add(x as u8);
+                let bg_y = state.scy.wrapping_add(state.ly);
+                let index = state.bg_tile(bg_x / 8, bg_y / 8);
+                state.tile_pixel(index, bg_x, bg_y)
+            };
+        }
+        window_drawn
+    }
+
+    // On DMG the object with the lowest X wins where objects overlap, then the
+    // one first in OAM. Its transparent pixels let the next one through, but
+    // if it's behind the background it hides the others too.
+    fn render_sprites(&self, state: &PpuState, line: &mut [u8]) {
+        let mut sprites = state.sprites.clone();
+        sprites.sort_by_key(|sprite| sprite.x);
+        let mut drawn = [false; SCREEN_WIDTH];
+
+        for sprite in &sprites {
+            let pixels = state.sprite_row(sprite);
+            for (i, colour) in pixels.iter().enumerate() {
+                // sprite.x is the screen position plus 8
+                let Some(x) = (sprite.x as usize + i).checked_sub(8) else {
+                    continue;
+                };
+                if x >= SCREEN_WIDTH || *colour == 0 || drawn[x] {
+                    continue;
+                }
+                drawn[x] = true;
+                if !(sprite.behind_bg() && self.bg_colours[x] != 0) {
+                    line[x] = state.sprite_shade(sprite, *colour);
+                }
+            }
+        }
+    }
+}
+
+impl Default for ScanlineRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Renderer for ScanlineRenderer {
+    fn start_frame(&mut self) {
+        self.window_line = 0;
+    }
+
+    fn start_line(&mut self, _state: &PpuState) {
+        self.dots = 0;
+    }
+
+    fn tick(&mut self, state: &PpuState, line: &mut [u8]) -> bool {
+        self.dots += 1;
+        if self.dots < PIXEL_TRANSFER_DOTS {
+            return false;
+        }
+        if self.render_background(state) {
+            self.window_line += 1;
+        }
+        for (pixel, colour) in line.iter_mut().zip(self.bg_colours) {
+            *pixel = state.bg_shade(colour);
+        }
+        if state.lcdc & LCDC_OBJ_ENABLE != 0 {
+            self.render_sprites(state, line);
+        }
+        true
+    }
+}
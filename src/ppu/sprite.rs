@@ -0,0 +1,92 @@
+use crate::ppu::{PpuState, LCDC_OBJ_SIZE};
+
+// Attribute flags
+pub const SPRITE_BEHIND_BG: u8 = 0x80;
+pub const SPRITE_Y_FLIP: u8 = 0x40;
+pub const SPRITE_X_FLIP: u8 = 0x20;
+pub const SPRITE_PALETTE: u8 = 0x10;
+
+// At most this many objects are drawn on one line.
+pub const SPRITES_PER_LINE: usize = 10;
+
+/// An OAM entry. `y` and `x` are the screen position plus 16 and 8, so an
+/// object at 0, 0 is entirely off screen.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Sprite {
+    pub y: u8,
+    pub x: u8,
+    pub tile: u8,
+    pub flags: u8,
+}
+
+impl Sprite {
+    pub fn from_oam(entry: &[u8]) -> Sprite {
+        Sprite {
+            y: entry[0],
+            x: entry[1],
+            tile: entry[2],
+            flags: entry[3],
+        }
+    }
+
+    /// Whether any of the object is on line `ly` when objects are `height`
+    /// pixels tall.
+    pub fn on_line(&self, ly: u8, height: u8) -> bool {
+        let top = self.y as i16 - 16;
+        (top..top + height as i16).contains(&(ly as i16))
+    }
+
+    pub fn behind_bg(&self) -> bool {
+        self.flags & SPRITE_BEHIND_BG != 0
+    }
+}
+
+impl PpuState {
+    /// Objects are 8x8, or 8x16 when LCDC bit 2 is set.
+    pub fn sprite_height(&self) -> u8 {
+        if self.lcdc & LCDC_OBJ_SIZE != 0 {
+            16
+        } else {
+            8
+        }
+    }
+
+    /// The colours (0 = transparent) of the eight pixels of `sprite` on the
+    /// current line, left to right, with flipping applied. Object tiles always
+    /// come from 8000; in 8x16 mode bit 0 of the tile number is ignored.
+    pub fn sprite_row(&self, sprite: &Sprite) -> [u8; 8] {
+        let height = self.sprite_height();
+        let mut row = (self.ly as i16 - (sprite.y as i16 - 16)) as u8 & (height - 1);
+        if sprite.flags & SPRITE_Y_FLIP != 0 {
+            row = height - 1 - row;
+        }
+        let tile = if height == 16 {
+            sprite.tile & 0xFE
+        } else {
+            sprite.tile
+        };
+        let address = tile as usize * 16 + row as usize * 2;
+        let (low, high) = (self.vram[address], self.vram[address + 1]);
+
+        let mut pixels = [0; 8];
+        for (i, pixel) in pixels.iter_mut().enumerate() {
+            let bit = if sprite.flags & SPRITE_X_FLIP != 0 {
+                i
+            } else {
+                7 - i
+            };
+            *pixel = ((high >> bit) & 1) << 1 | (low >> bit) & 1;
+        }
+        pixels
+    }
+
+    /// Applies OBP0 or OBP1, whichever `sprite` uses, to one of its colours.
+    pub fn sprite_shade(&self, sprite: &Sprite, colour: u8) -> u8 {
+        let palette = if sprite.flags & SPRITE_PALETTE != 0 {
+            self.obp1
+        } else {
+            self.obp0
+        };
+        (palette >> (colour * 2)) & 0x03
+    }
+}
@@ -0,0 +1,210 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::interrupts::Interrupt;
+
+pub const SB_ADDRESS: u16 = 0xFF01;
+pub const SC_ADDRESS: u16 = 0xFF02;
+
+pub const SC_TRANSFER: u8 = 0x80;
+pub const SC_INTERNAL_CLOCK: u8 = 0x01;
+
+// The internal clock runs at 8192 Hz, so each bit takes 128 M-cycles.
+const TRANSFER_CYCLES: u16 = 8 * 128;
+
+/// Whatever is on the other end of the link cable.
+pub trait SerialEndpoint {
+    /// This side has clocked a whole byte out on its internal clock. Returns
+    /// the byte clocked in from the other end, 0xFF if nothing drives the line.
+    fn exchange(&mut self, byte: u8) -> u8;
+
+    /// Polled every M-cycle while this side waits for the other end to drive
+    /// the clock, with SB as the byte to send back. Returns the byte received
+    /// once the other end has clocked a transfer.
+    fn external_transfer(&mut self, _outgoing: u8) -> Option<u8> {
+        None
+    }
+
+    /// This side has stopped waiting on the external clock, by clearing SC
+    /// bit 7 or switching to the internal clock.
+    fn cancel_transfer(&mut self) {}
+}
+
+/// No cable. Internally clocked transfers read all 1s and externally clocked
+/// ones never finish.
+pub struct NullEndpoint;
+
+impl SerialEndpoint for NullEndpoint {
+    fn exchange(&mut self, _byte: u8) -> u8 {
+        0xFF
+    }
+}
+
+/// Records every byte sent, e.g. the results test ROMs print over serial.
+/// Clones share the same buffer, so keep one to read it back after handing
+/// the other to the serial port.
+#[derive(Clone, Default)]
+pub struct CaptureSink {
+    bytes: Rc<RefCell<Vec<u8>>>,
+}
+
+impl CaptureSink {
+    pub fn new() -> CaptureSink {
+        CaptureSink::default()
+    }
+
+    pub fn bytes(&self) -> Vec<u8> {
+        self.bytes.borrow().clone()
+    }
+
+    /// The bytes sent so far as text, with anything that isn't UTF-8 replaced.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.bytes.borrow()).into_owned()
+    }
+}
+
+impl SerialEndpoint for CaptureSink {
+    fn exchange(&mut self, byte: u8) -> u8 {
+        self.bytes.borrow_mut().push(byte);
+        0xFF
+    }
+}
+
+#[derive(Default)]
+struct LinkSide {
+    // SB of a side waiting on the external clock.
+    waiting: Option<u8>,
+    // A byte clocked in by the other side, not yet picked up.
+    received: Option<u8>,
+}
+
+/// One end of a cable between two emulated Game Boys, made by `link_cable`.
+pub struct LinkPort {
+    sides: Rc<RefCell<[LinkSide; 2]>>,
+    side: usize,
+}
+
+/// Two connected ports, one for each Game Boy's serial port. A transfer goes
+/// through when one side clocks it while the other is waiting with an
+/// external clock; if the other side isn't waiting, it reads 0xFF.
+pub fn link_cable() -> (LinkPort, LinkPort) {
+    let sides = Rc::new(RefCell::new([LinkSide::default(), LinkSide::default()]));
+    (
+        LinkPort {
+            sides: sides.clone(),
+            side: 0,
+        },
+        LinkPort { sides, side: 1 },
+    )
+}
+
+impl SerialEndpoint for LinkPort {
+    fn exchange(&mut self, byte: u8) -> u8 {
+        let mut sides = self.sides.borrow_mut();
+        let other = &mut sides[1 - self.side];
+        match other.waiting.take() {
+            Some(incoming) => {
+                other.received = Some(byte);
+                incoming
+            }
+            None => 0xFF,
+        }
+    }
+
+    fn external_transfer(&mut self, outgoing: u8) -> Option<u8> {
+        let mut sides = self.sides.borrow_mut();
+        let side = &mut sides[self.side];
+        let received = side.received.take();
+        side.waiting = match received {
+            Some(_) => None,
+            None => Some(outgoing),
+        };
+        received
+    }
+
+    fn cancel_transfer(&mut self) {
+        // a byte the other side has already clocked in is still picked up
+        self.sides.borrow_mut()[self.side].waiting = None;
+    }
+}
+
+/// The serial port: SB holds the byte to send and, once the transfer is done,
+/// the byte received. Setting bit 7 of SC starts a transfer, clocked by this
+/// side at 8192 Hz if bit 0 is set or by the other end otherwise, and the
+/// interrupt is requested when it finishes. The byte is exchanged whole at
+/// the end of the transfer rather than a bit at a time.
+pub struct Serial {
+    sb: u8,
+    sc: u8,
+    endpoint: Box<dyn SerialEndpoint>,
+    cycles: u16,
+}
+
+impl Serial {
+    pub fn new() -> Serial {
+        Serial {
+            sb: 0,
+            sc: 0,
+            endpoint: Box::new(NullEndpoint),
+            cycles: 0,
+        }
+    }
+
+    pub fn set_endpoint(&mut self, endpoint: Box<dyn SerialEndpoint>) {
+        self.endpoint = endpoint;
+    }
+
+    pub fn read(&self, address: u16) -> u8 {
+        match address {
+            SB_ADDRESS => self.sb,
+            // bits 1-6 are unused on the DMG and read as 1
+            SC_ADDRESS => 0x7E | self.sc,
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        match address {
+            SB_ADDRESS => self.sb = value,
+            SC_ADDRESS => {
+                let sc = value & (SC_TRANSFER | SC_INTERNAL_CLOCK);
+                // stopping an externally clocked transfer or switching it to
+                // the internal clock, rather than writing the same value again
+                if self.sc == SC_TRANSFER && sc != SC_TRANSFER {
+                    self.endpoint.cancel_transfer();
+                }
+                self.sc = sc;
+                self.cycles = 0;
+            }
+            _ => {}
+        }
+    }
+
+    /// Advances by one M-cycle, returning the interrupts raised (as IF bits).
+    pub fn tick(&mut self) -> u8 {
+        if self.sc & SC_TRANSFER == 0 {
+            return 0;
+        }
+        let received = if self.sc & SC_INTERNAL_CLOCK != 0 {
+            self.cycles += 1;
+            if self.cycles < TRANSFER_CYCLES {
+                return 0;
+            }
+            self.endpoint.exchange(self.sb)
+        } else {
+            match self.endpoint.external_transfer(self.sb) {
+                Some(byte) => byte,
+                None => return 0,
+            }
+        };
+        self.sb = received;
+        self.sc &= !SC_TRANSFER;
+        Interrupt::Serial.mask()
+    }
+}
+
+impl Default for Serial {
+    fn default() -> Self {
+        Serial::new()
+    }
+}
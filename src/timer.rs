@@ -0,0 +1,133 @@
+use crate::interrupts::Interrupt;
+
+pub const DIV_ADDRESS: u16 = 0xFF04;
+pub const TIMA_ADDRESS: u16 = 0xFF05;
+pub const TMA_ADDRESS: u16 = 0xFF06;
+pub const TAC_ADDRESS: u16 = 0xFF07;
+
+pub const TAC_ENABLE: u8 = 0x04;
+const TAC_WRITABLE: u8 = 0x07;
+
+// The bit of the system counter whose falling edge increments TIMA, for each
+// TAC clock select (4096, 262144, 65536 and 16384 Hz).
+const TAC_COUNTER_BITS: [u16; 4] = [9, 3, 5, 7];
+
+/// The divider and timer. A 16-bit system counter runs at the 4 MHz clock and
+/// DIV is its upper byte. TIMA doesn't have a clock of its own: it counts
+/// falling edges of one counter bit (picked by TAC) ANDed with the TAC enable
+/// bit, which is why resetting DIV or changing TAC can increment it.
+///
+/// When TIMA overflows it reads 0 for one M-cycle before it's reloaded from
+/// TMA and the interrupt is requested. Writing TIMA during that cycle cancels
+/// the reload; during the reload cycle itself TIMA writes are ignored and
+/// TMA writes go through to TIMA as well.
+pub struct Timer {
+    counter: u16,
+    tima: u8,
+    tma: u8,
+    tac: u8,
+    // TIMA overflowed in the last M-cycle and gets reloaded in the next one.
+    overflowed: bool,
+    // TIMA was reloaded from TMA in this M-cycle.
+    reloading: bool,
+}
+
+impl Timer {
+    /// A timer in the state the DMG boot ROM leaves it in.
+    pub fn new() -> Timer {
+        Timer {
+            counter: 0xABCC,
+            tima: 0,
+            tma: 0,
+            tac: 0,
+            overflowed: false,
+            reloading: false,
+        }
+    }
+
+    /// The full 16-bit system counter.
+    pub fn counter(&self) -> u16 {
+        self.counter
+    }
+
+    pub fn read(&self, address: u16) -> u8 {
+        match address {
+            DIV_ADDRESS => (self.counter >> 8) as u8,
+            TIMA_ADDRESS => self.tima,
+            TMA_ADDRESS => self.tma,
+            // the unused upper bits read as 1
+            TAC_ADDRESS => 0xF8 | self.tac,
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        match address {
+            DIV_ADDRESS => {
+                let signal = self.signal();
+                self.counter = 0;
+                self.detect_falling_edge(signal);
+            }
+            TIMA_ADDRESS if self.reloading => {}
+            TIMA_ADDRESS => {
+                self.tima = value;
+                self.overflowed = false;
+            }
+            TMA_ADDRESS => {
+                self.tma = value;
+                if self.reloading {
+                    self.tima = value;
+                }
+            }
+            TAC_ADDRESS => {
+                let signal = self.signal();
+                self.tac = value & TAC_WRITABLE;
+                self.detect_falling_edge(signal);
+            }
+            _ => {}
+        }
+    }
+
+    /// Advances by one M-cycle, returning the interrupts raised (as IF bits).
+    pub fn tick(&mut self) -> u8 {
+        let mut interrupts = 0;
+        self.reloading = false;
+        if self.overflowed {
+            self.overflowed = false;
+            self.reloading = true;
+            self.tima = self.tma;
+            interrupts |= Interrupt::Timer.mask();
+        }
+
+        let signal = self.signal();
+        self.counter = self.counter.wrapping_add(4);
+        self.detect_falling_edge(signal);
+        interrupts
+    }
+
+    // The input to TIMA's edge detector.
+    fn signal(&self) -> bool {
+        let bit = TAC_COUNTER_BITS[(self.tac & 0x03) as usize];
+        self.tac & TAC_ENABLE != 0 && self.counter & (1 << bit) != 0
+    }
+
+    fn detect_falling_edge(&mut self, was_high: bool) {
+        if was_high && !self.signal() {
+            self.increment_tima();
+        }
+    }
+
+    fn increment_tima(&mut self) {
+        let (tima, overflowed) = self.tima.overflowing_add(1);
+        self.tima = tima;
+        if overflowed {
+            self.overflowed = true;
+        }
+    }
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Timer::new()
+    }
+}
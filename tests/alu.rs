@@ -4,6 +4,7 @@ use helpers::*;
 
 use rustyboy::cpu::instructions::{ArithmeticTarget8, Instruction};
 use rustyboy::cpu::new_cpu;
+use rustyboy::cpu::registers::FlagsRegister;
 
 #[test]
 fn test_add() {
@@ -17,3 +18,162 @@ fn test_add() {
         assert_eq!(result_value::<u8>(&result), cpu.registers.a);
     })
 }
+
+// Runs every entry of an sm83-test-data ALU suite with x in A, y in B and the
+// entry's flags in F, then checks both A and F.
+fn run_suite(name: &str, instruction: Instruction) {
+    let mut cpu = new_cpu();
+    let filename = format!("sm83-test-data/alu_tests/v1/{}.json", name);
+    stream_json_from_array_file(&filename, |result| {
+        cpu.reset();
+        cpu.registers.a = x_as(&result);
+        cpu.registers.b = y_as(&result);
+        cpu.registers.f = FlagsRegister::from(flags_as::<u8>(&result));
+        cpu.execute(instruction);
+
+        assert_eq!(
+            result_value::<u8>(&result),
+            cpu.registers.a,
+            "{} {:?}",
+            name,
+            result
+        );
+        assert_eq!(
+            result_flags::<u8>(&result),
+            u8::from(cpu.registers.f),
+            "{} {:?}",
+            name,
+            result
+        );
+    })
+}
+
+#[test]
+fn test_add_flags() {
+    run_suite("add", Instruction::ADDr(ArithmeticTarget8::B));
+}
+
+#[test]
+fn test_adc() {
+    run_suite("adc", Instruction::ADCr(ArithmeticTarget8::B));
+}
+
+#[test]
+fn test_sub() {
+    run_suite("sub", Instruction::SUBr(ArithmeticTarget8::B));
+}
+
+#[test]
+fn test_sbc() {
+    run_suite("sbc", Instruction::SBCr(ArithmeticTarget8::B));
+}
+
+#[test]
+fn test_and() {
+    run_suite("and", Instruction::ANDr(ArithmeticTarget8::B));
+}
+
+#[test]
+fn test_xor() {
+    run_suite("xor", Instruction::XORr(ArithmeticTarget8::B));
+}
+
+#[test]
+fn test_or() {
+    run_suite("or", Instruction::ORr(ArithmeticTarget8::B));
+}
+
+#[test]
+fn test_cp() {
+    run_suite("cp", Instruction::CPr(ArithmeticTarget8::B));
+}
+
+#[test]
+fn test_inc() {
+    run_suite("inc", Instruction::INCr(ArithmeticTarget8::A));
+}
+
+#[test]
+fn test_dec() {
+    run_suite("dec", Instruction::DECr(ArithmeticTarget8::A));
+}
+
+#[test]
+fn test_rlca() {
+    run_suite("rlca", Instruction::RLCA);
+}
+
+#[test]
+fn test_rla() {
+    run_suite("rla", Instruction::RLA);
+}
+
+#[test]
+fn test_rrca() {
+    run_suite("rrca", Instruction::RRCA);
+}
+
+#[test]
+fn test_rra() {
+    run_suite("rra", Instruction::RRA);
+}
+
+#[test]
+fn test_rlc() {
+    run_suite("rlc", Instruction::RLCr(ArithmeticTarget8::A));
+}
+
+#[test]
+fn test_rl() {
+    run_suite("rl", Instruction::RLr(ArithmeticTarget8::A));
+}
+
+#[test]
+fn test_rrc() {
+    run_suite("rrc", Instruction::RRCr(ArithmeticTarget8::A));
+}
+
+#[test]
+fn test_rr() {
+    run_suite("rr", Instruction::RRr(ArithmeticTarget8::A));
+}
+
+#[test]
+fn test_sla() {
+    run_suite("sla", Instruction::SLAr(ArithmeticTarget8::A));
+}
+
+#[test]
+fn test_sra() {
+    run_suite("sra", Instruction::SRAr(ArithmeticTarget8::A));
+}
+
+#[test]
+fn test_srl() {
+    run_suite("srl", Instruction::SRLr(ArithmeticTarget8::A));
+}
+
+#[test]
+fn test_swap() {
+    run_suite("swap", Instruction::SWAPr(ArithmeticTarget8::A));
+}
+
+#[test]
+fn test_cpl() {
+    run_suite("cpl", Instruction::CPL);
+}
+
+#[test]
+fn test_scf() {
+    run_suite("scf", Instruction::SCF);
+}
+
+#[test]
+fn test_ccf() {
+    run_suite("ccf", Instruction::CCF);
+}
+
+#[test]
+fn test_daa() {
+    run_suite("daa", Instruction::DAA);
+}
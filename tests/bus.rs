@@ -0,0 +1,135 @@
+use rustyboy::bus::{Bus, FlatRam, MemoryMap};
+use rustyboy::cartridge::RomOnly;
+use rustyboy::cpu::instructions::{ArithmeticTarget16, Instruction};
+use rustyboy::cpu::{new_cpu, new_cpu_with_bus};
+
+#[derive(Debug, PartialEq)]
+enum Access {
+    Read(u16, u8),
+    Write(u16, u8),
+}
+
+struct RecordingBus {
+    ram: FlatRam,
+    accesses: Vec<Access>,
+}
+
+impl Bus for RecordingBus {
+    fn read(&mut self, address: u16) -> u8 {
+        let value = self.ram.read(address);
+        self.accesses.push(Access::Read(address, value));
+        value
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        self.accesses.push(Access::Write(address, value));
+        self.ram.write(address, value);
+    }
+}
+
+#[test]
+fn test_push_pop_roundtrip() {
+    let mut cpu = new_cpu();
+    cpu.execute(Instruction::LDrrnn(ArithmeticTarget16::SP, 0xFFFE));
+    cpu.execute(Instruction::LDrrnn(ArithmeticTarget16::BC, 0x1234));
+    cpu.execute(Instruction::PUSH(ArithmeticTarget16::BC));
+    cpu.execute(Instruction::POP(ArithmeticTarget16::DE));
+
+    assert_eq!(0x1234, cpu.registers.get_de());
+    assert_eq!(0xFFFE, cpu.registers.sp);
+    assert_eq!(0x12, cpu.bus.read(0xFFFD));
+    assert_eq!(0x34, cpu.bus.read(0xFFFC));
+}
+
+#[test]
+fn test_push_pop_access_order() {
+    let mut cpu = new_cpu_with_bus(RecordingBus {
+        ram: FlatRam::new(),
+        accesses: Vec::new(),
+    });
+    cpu.execute(Instruction::LDrrnn(ArithmeticTarget16::SP, 0xD000));
+    cpu.execute(Instruction::LDrrnn(ArithmeticTarget16::HL, 0xBEEF));
+    cpu.execute(Instruction::PUSH(ArithmeticTarget16::HL));
+    cpu.execute(Instruction::POP(ArithmeticTarget16::AF));
+
+    assert_eq!(
+        vec![
+            Access::Write(0xCFFF, 0xBE),
+            Access::Write(0xCFFE, 0xEF),
+            Access::Read(0xCFFE, 0xEF),
+            Access::Read(0xCFFF, 0xBE),
+        ],
+        cpu.bus.accesses
+    );
+    // the low nibble of F doesn't exist
+    assert_eq!(0xBEE0, cpu.registers.get_af());
+}
+
+#[test]
+fn test_memory_map() {
+    let mut rom = vec![0; 0x8000];
+    rom[0x0100] = 0x42;
+    let mut bus = MemoryMap::new(Box::new(RomOnly::new(rom, 0)));
+
+    assert_eq!(0x42, bus.read(0x0100));
+    bus.write(0x0100, 0x00);
+    assert_eq!(0x42, bus.read(0x0100));
+
+    bus.write(0xC123, 0x99);
+    assert_eq!(0x99, bus.read(0xE123));
+    bus.write(0xFDFF, 0x77);
+    assert_eq!(0x77, bus.read(0xDDFF));
+
+    bus.write(0xFFFF, 0x1F);
+    assert_eq!(0x1F, bus.read(0xFFFF));
+    bus.write(0xFF80, 0x01);
+    assert_eq!(0x01, bus.read(0xFF80));
+}
+
+#[test]
+fn test_hl_operand_read_modify_write() {
+    let mut ram = FlatRam::new();
+    ram.load(
+        0x0100,
+        &[
+            0x34, // INC (HL)
+            0xCB, 0x06, // RLC (HL)
+            0x96, // SUB (HL)
+            0xCB, 0x46, // BIT 0,(HL)
+            0xCB, 0xFE, // SET 7,(HL)
+        ],
+    );
+    ram.write(0xC000, 0x40);
+    let mut cpu = new_cpu_with_bus(RecordingBus {
+        ram,
+        accesses: Vec::new(),
+    });
+    cpu.pc = 0x0100;
+    cpu.registers.set_hl(0xC000);
+    cpu.registers.a = 0x90;
+
+    let cycles: Vec<u8> = (0..5).map(|_| cpu.step().unwrap()).collect();
+    assert_eq!(vec![3, 4, 2, 3, 4], cycles);
+
+    let operand_accesses: Vec<&Access> = cpu
+        .bus
+        .accesses
+        .iter()
+        .filter(|access| matches!(access, Access::Read(0xC000, _) | Access::Write(0xC000, _)))
+        .collect();
+    assert_eq!(
+        vec![
+            &Access::Read(0xC000, 0x40),
+            &Access::Write(0xC000, 0x41),
+            &Access::Read(0xC000, 0x41),
+            &Access::Write(0xC000, 0x82),
+            &Access::Read(0xC000, 0x82),
+            &Access::Read(0xC000, 0x82),
+            &Access::Read(0xC000, 0x82),
+            &Access::Write(0xC000, 0x82),
+        ],
+        operand_accesses
+    );
+    assert_eq!(0x0E, cpu.registers.a);
+    assert!(cpu.registers.f.zero);
+}
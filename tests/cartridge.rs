@@ -0,0 +1,117 @@
+mod helpers;
+
+use helpers::*;
+
+use rustyboy::cartridge::{Cartridge, CartridgeError, CgbSupport, Header, Licensee, MapperKind};
+
+#[test]
+fn test_parse_header() {
+    let mut rom = build_rom(0x13, 0x05, 0x03);
+    rom[0x0143] = 0x80;
+    rom[0x0146] = 0x03;
+    rom[0x014A] = 0x01;
+    rom[0x014B] = 0x33;
+    rom[0x0144..0x0146].copy_from_slice(b"01");
+    rom[0x014C] = 0x02;
+
+    let header = Header::parse(&rom).unwrap();
+    assert_eq!("TEST", header.title);
+    assert_eq!(CgbSupport::Compatible, header.cgb);
+    assert!(header.sgb);
+    assert_eq!(Licensee::New("01".to_string()), header.licensee);
+    assert_eq!(0x13, header.cartridge_type);
+    assert_eq!(MapperKind::Mbc3, header.mapper);
+    assert!(header.has_ram);
+    assert!(header.has_battery);
+    assert!(!header.has_timer);
+    assert_eq!(1024 * 1024, header.rom_size);
+    assert_eq!(32 * 1024, header.ram_size);
+    assert!(!header.japanese);
+    assert_eq!(2, header.version);
+
+    // the checksums were computed before the edits above
+    assert!(matches!(
+        header.validate(&rom),
+        Err(CartridgeError::HeaderChecksum { .. })
+    ));
+}
+
+#[test]
+fn test_load_valid_rom() {
+    let rom = build_rom(0x00, 0x00, 0x00);
+    let cartridge = Cartridge::from_rom(rom.clone()).unwrap();
+
+    assert_eq!(MapperKind::None, cartridge.header.mapper);
+    assert_eq!(Licensee::Old(0x00), cartridge.header.licensee);
+    assert_eq!(CgbSupport::None, cartridge.header.cgb);
+    assert_eq!(Ok(()), cartridge.header.validate(&rom));
+}
+
+#[test]
+fn test_malformed_roms() {
+    assert_eq!(
+        Some(CartridgeError::TooSmall(0x100)),
+        Cartridge::from_rom(vec![0; 0x100]).err()
+    );
+
+    let mut rom = build_rom(0x00, 0x00, 0x00);
+    rom[0x0110] ^= 0xFF;
+    assert_eq!(
+        Some(CartridgeError::BadLogo),
+        Cartridge::from_rom(rom).err()
+    );
+
+    let mut rom = build_rom(0x00, 0x00, 0x00);
+    rom[0x014D] = rom[0x014D].wrapping_add(1);
+    assert!(matches!(
+        Cartridge::from_rom(rom).err(),
+        Some(CartridgeError::HeaderChecksum { .. })
+    ));
+
+    let mut rom = build_rom(0x00, 0x00, 0x00);
+    rom[0x0147] = 0x42;
+    assert_eq!(
+        Some(CartridgeError::UnknownCartridgeType(0x42)),
+        Cartridge::from_rom(rom).err()
+    );
+
+    let mut rom = build_rom(0x00, 0x00, 0x00);
+    rom.truncate(0x4000);
+    assert_eq!(
+        Some(CartridgeError::RomSizeMismatch {
+            header: 0x8000,
+            actual: 0x4000
+        }),
+        Cartridge::from_rom(rom).err()
+    );
+}
+
+#[test]
+fn test_global_checksum_is_only_checked_by_validate() {
+    let mut rom = build_rom(0x00, 0x00, 0x00);
+    rom[0x4000] = 0xAA;
+
+    let cartridge = Cartridge::from_rom(rom.clone()).unwrap();
+    assert!(matches!(
+        cartridge.header.validate(&rom),
+        Err(CartridgeError::GlobalChecksum { .. })
+    ));
+}
+
+#[test]
+fn test_every_cartridge_type_loads_except_tama5() {
+    let types = [
+        0x00, 0x01, 0x02, 0x03, 0x05, 0x06, 0x08, 0x09, 0x0B, 0x0C, 0x0D, 0x0F, 0x10, 0x11, 0x12,
+        0x13, 0x19, 0x1A, 0x1B, 0x1C, 0x1D, 0x1E, 0x20, 0x22, 0xFC, 0xFE, 0xFF,
+    ];
+    for cartridge_type in types {
+        let rom = build_rom(cartridge_type, 0x02, 0x03);
+        assert!(Cartridge::from_rom(rom).is_ok(), "{:#04X}", cartridge_type);
+    }
+
+    let error = Cartridge::from_rom(build_rom(0xFD, 0x02, 0x00))
+        .err()
+        .unwrap();
+    assert_eq!(CartridgeError::UnsupportedCartridgeType(0xFD), error);
+    assert_eq!("cartridge type 0xFD isn't supported", error.to_string());
+}
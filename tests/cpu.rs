@@ -0,0 +1,183 @@
+mod helpers;
+
+use helpers::*;
+
+use rustyboy::cpu::decoder::DecodeError;
+
+#[test]
+fn test_step() {
+    let mut cpu = cpu_with_program(&[
+        0x3E, 0x05, // LD A,5
+        0x06, 0x03, // LD B,3
+        0x80, // ADD A,B
+        0x00, // NOP
+    ]);
+
+    assert_eq!(Ok(2), cpu.step());
+    assert_eq!(0x0102, cpu.pc);
+    assert_eq!(Ok(2), cpu.step());
+    assert_eq!(Ok(1), cpu.step());
+    assert_eq!(8, cpu.registers.a);
+    assert_eq!(Ok(1), cpu.step());
+    assert_eq!(0x0106, cpu.pc);
+}
+
+#[test]
+fn test_step_illegal_opcode() {
+    let mut cpu = cpu_with_program(&[0x00, 0xD3]);
+
+    assert_eq!(Ok(1), cpu.step());
+    assert_eq!(Err(DecodeError::IllegalOpcode(0xD3)), cpu.step());
+    assert_eq!(0x0101, cpu.pc);
+}
+
+#[test]
+fn test_run_for_cycles() {
+    let mut cpu = cpu_with_program(&[0x00, 0x00, 0x01, 0x34, 0x12, 0x00]);
+
+    assert_eq!(Ok(2), cpu.run_for_cycles(2));
+    // LD BC,nn takes three cycles so this overshoots
+    assert_eq!(Ok(3), cpu.run_for_cycles(1));
+    assert_eq!(0x1234, cpu.registers.get_bc());
+    assert_eq!(0x0105, cpu.pc);
+}
+
+#[test]
+fn test_jumps() {
+    let mut cpu = cpu_with_program(&[
+        0xC3, 0x10, 0x01, // 0100: JP 0110
+    ]);
+    assert_eq!(Ok(4), cpu.step());
+    assert_eq!(0x0110, cpu.pc);
+
+    cpu.bus.load(0x0110, &[0x18, 0xFC]); // JR -4
+    assert_eq!(Ok(3), cpu.step());
+    assert_eq!(0x010E, cpu.pc);
+
+    cpu.registers.set_hl(0x4000);
+    cpu.bus.load(0x010E, &[0xE9]); // JP (HL)
+    assert_eq!(Ok(1), cpu.step());
+    assert_eq!(0x4000, cpu.pc);
+}
+
+#[test]
+fn test_conditional_jumps() {
+    let mut cpu = cpu_with_program(&[
+        0x28, 0x10, // JR Z,+16
+        0x20, 0x10, // JR NZ,+16
+    ]);
+    cpu.registers.f.zero = false;
+
+    assert_eq!(Ok(2), cpu.step());
+    assert_eq!(0x0102, cpu.pc);
+    assert_eq!(Ok(3), cpu.step());
+    assert_eq!(0x0114, cpu.pc);
+
+    cpu.bus.load(0x0114, &[0xDA, 0x00, 0x20, 0xD2, 0x00, 0x20]); // JP C / JP NC
+    cpu.registers.f.carry = false;
+    assert_eq!(Ok(3), cpu.step());
+    assert_eq!(0x0117, cpu.pc);
+    assert_eq!(Ok(4), cpu.step());
+    assert_eq!(0x2000, cpu.pc);
+}
+
+#[test]
+fn test_call_and_return() {
+    let mut cpu = cpu_with_program(&[
+        0xCD, 0x00, 0x02, // 0100: CALL 0200
+        0xC4, 0x00, 0x03, // 0103: CALL NZ,0300
+        0xCC, 0x00, 0x03, // 0106: CALL Z,0300
+    ]);
+    cpu.registers.sp = 0xFFFE;
+    cpu.bus.load(0x0200, &[0xC9]); // RET
+    cpu.bus.load(0x0300, &[0xC8, 0xC0]); // RET Z, RET NZ
+
+    assert_eq!(Ok(6), cpu.step());
+    assert_eq!(0x0200, cpu.pc);
+    assert_eq!(0xFFFC, cpu.registers.sp);
+    assert_eq!(0x01, cpu.bus.memory[0xFFFD]);
+    assert_eq!(0x03, cpu.bus.memory[0xFFFC]);
+    assert_eq!(Ok(4), cpu.step());
+    assert_eq!(0x0103, cpu.pc);
+    assert_eq!(0xFFFE, cpu.registers.sp);
+
+    cpu.registers.f.zero = false;
+    assert_eq!(Ok(6), cpu.step());
+    assert_eq!(0x0300, cpu.pc);
+    assert_eq!(Ok(2), cpu.step());
+    assert_eq!(0x0301, cpu.pc);
+    assert_eq!(Ok(5), cpu.step());
+    assert_eq!(0x0106, cpu.pc);
+
+    assert_eq!(Ok(3), cpu.step());
+    assert_eq!(0x0109, cpu.pc);
+    assert_eq!(0xFFFE, cpu.registers.sp);
+}
+
+#[test]
+fn test_restart() {
+    let mut cpu = cpu_with_program(&[0xEF]); // RST 28h
+    cpu.registers.sp = 0xD000;
+
+    assert_eq!(Ok(4), cpu.step());
+    assert_eq!(0x0028, cpu.pc);
+    assert_eq!(
+        0x0101,
+        u16::from_le_bytes([cpu.bus.memory[0xCFFE], cpu.bus.memory[0xCFFF]])
+    );
+}
+
+#[test]
+fn test_add_hl_flags() {
+    let mut cpu = cpu_with_program(&[
+        0x09, // ADD HL,BC
+        0x29, // ADD HL,HL
+    ]);
+    cpu.registers.set_hl(0x0FFF);
+    cpu.registers.set_bc(0x0001);
+    cpu.registers.f.zero = true;
+    cpu.registers.f.subtract = true;
+
+    assert_eq!(Ok(2), cpu.step());
+    assert_eq!(0x1000, cpu.registers.get_hl());
+    assert!(cpu.registers.f.zero);
+    assert!(!cpu.registers.f.subtract);
+    assert!(cpu.registers.f.half_carry);
+    assert!(!cpu.registers.f.carry);
+
+    cpu.registers.set_hl(0x8000);
+    assert_eq!(Ok(2), cpu.step());
+    assert_eq!(0x0000, cpu.registers.get_hl());
+    assert!(cpu.registers.f.zero);
+    assert!(!cpu.registers.f.half_carry);
+    assert!(cpu.registers.f.carry);
+}
+
+#[test]
+fn test_daa() {
+    let mut cpu = cpu_with_program(&[
+        0xC6, 0x27, // ADD A,27h
+        0x27, // DAA
+        0xD6, 0x43, // SUB A,43h
+        0x27, // DAA
+        0xC6, 0x01, // ADD A,01h
+        0x27, // DAA
+    ]);
+    cpu.registers.a = 0x15;
+
+    cpu.run_for_cycles(3).unwrap();
+    assert_eq!(0x42, cpu.registers.a);
+    assert!(!cpu.registers.f.carry);
+
+    // 42 - 43 borrows
+    cpu.run_for_cycles(3).unwrap();
+    assert_eq!(0x99, cpu.registers.a);
+    assert!(cpu.registers.f.carry);
+    assert!(cpu.registers.f.subtract);
+
+    cpu.run_for_cycles(3).unwrap();
+    assert_eq!(0x00, cpu.registers.a);
+    assert!(cpu.registers.f.zero);
+    assert!(cpu.registers.f.carry);
+    assert!(!cpu.registers.f.half_carry);
+}
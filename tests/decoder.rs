@@ -0,0 +1,108 @@
+use rustyboy::cpu::decoder::{decode, DecodeError, ILLEGAL_OPCODES, PREFIX_CB};
+use rustyboy::cpu::instructions::{
+    ArithmeticTarget16, ArithmeticTarget8, Condition, IndirectTarget, Instruction,
+};
+
+#[test]
+fn test_decodes_every_legal_opcode() {
+    for opcode in 0..=0xFFu8 {
+        let bytes = [opcode, 0x34, 0x12];
+        match decode(&bytes) {
+            Ok((_, length)) => {
+                assert!(!ILLEGAL_OPCODES.contains(&opcode));
+                assert!((1..=3).contains(&length), "{:#04X}", opcode);
+            }
+            Err(e) => assert_eq!(DecodeError::IllegalOpcode(opcode), e),
+        }
+    }
+}
+
+#[test]
+fn test_decodes_every_prefixed_opcode() {
+    for opcode in 0..=0xFFu8 {
+        let (_, length) = decode(&[PREFIX_CB, opcode]).unwrap();
+        assert_eq!(2, length);
+    }
+}
+
+#[test]
+fn test_illegal_opcodes() {
+    let illegal = (0..=0xFFu8)
+        .filter(|opcode| decode(&[*opcode, 0, 0]).is_err())
+        .count();
+    assert_eq!(11, illegal);
+    assert_eq!(Err(DecodeError::IllegalOpcode(0xDD)), decode(&[0xDD]));
+}
+
+#[test]
+fn test_unexpected_end() {
+    assert_eq!(
+        Err(DecodeError::UnexpectedEnd {
+            needed: 1,
+            available: 0
+        }),
+        decode(&[])
+    );
+    assert_eq!(
+        Err(DecodeError::UnexpectedEnd {
+            needed: 3,
+            available: 2
+        }),
+        decode(&[0xC3, 0x00])
+    );
+    assert!(decode(&[PREFIX_CB]).is_err());
+}
+
+#[test]
+fn test_decode_samples() {
+    let cases: [(&[u8], Instruction, u16); 20] = [
+        (&[0x00], Instruction::NOP, 1),
+        (
+            &[0x01, 0x34, 0x12],
+            Instruction::LDrrnn(ArithmeticTarget16::BC, 0x1234),
+            3,
+        ),
+        (&[0x08, 0xFE, 0xFF], Instruction::LDnnSP(0xFFFE), 3),
+        (&[0x10, 0x00], Instruction::STOP, 2),
+        (&[0x22], Instruction::LDindA(IndirectTarget::HLInc), 1),
+        (&[0x3A], Instruction::LDAind(IndirectTarget::HLDec), 1),
+        (
+            &[0x36, 0x42],
+            Instruction::LDri(ArithmeticTarget8::HLI, 0x42),
+            2,
+        ),
+        (&[0x38, 0xFE], Instruction::JRcce(Condition::C, -2), 2),
+        (
+            &[0x41],
+            Instruction::LDrr(ArithmeticTarget8::B, ArithmeticTarget8::C),
+            1,
+        ),
+        (&[0x76], Instruction::HALT, 1),
+        (&[0x86], Instruction::ADDr(ArithmeticTarget8::HLI), 1),
+        (&[0xBF], Instruction::CPr(ArithmeticTarget8::A), 1),
+        (
+            &[0xC4, 0x00, 0x40],
+            Instruction::CALLccnn(Condition::NZ, 0x4000),
+            3,
+        ),
+        (&[0xE0, 0x44], Instruction::LDHnA(0x44), 2),
+        (&[0xE8, 0x80], Instruction::ADDSPe(-128), 2),
+        (&[0xF1], Instruction::POP(ArithmeticTarget16::AF), 1),
+        (&[0xFF], Instruction::RSTn(0x38), 1),
+        (&[0xCB, 0x37], Instruction::SWAPr(ArithmeticTarget8::A), 2),
+        (
+            &[0xCB, 0x7E],
+            Instruction::BITnr(7, ArithmeticTarget8::HLI),
+            2,
+        ),
+        (
+            &[0xCB, 0xC0],
+            Instruction::SETnr(0, ArithmeticTarget8::B),
+            2,
+        ),
+    ];
+
+    for (bytes, instruction, length) in cases {
+        assert_eq!(Ok((instruction, length)), decode(bytes));
+    }
+}
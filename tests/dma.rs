@@ -0,0 +1,125 @@
+mod helpers;
+
+use helpers::*;
+
+use rustyboy::bus::{Bus, MemoryMap};
+use rustyboy::cpu::new_cpu_with_bus;
+
+// A memory map with the LCD off, so the PPU never locks OAM itself.
+fn lcd_off(rom: Vec<u8>) -> MemoryMap {
+    let mut bus = memory_map(rom);
+    bus.write(0xFF40, 0x11);
+    bus
+}
+
+fn fill_page(bus: &mut MemoryMap, page: u16, seed: u8) {
+    for i in 0..0xA0 {
+        bus.write(page + i, seed.wrapping_add(i as u8));
+    }
+}
+
+#[test]
+fn test_dma_copies_to_oam() {
+    let mut bus = lcd_off(vec![0; 0x8000]);
+    fill_page(&mut bus, 0xC000, 0x10);
+    bus.write(0xFF80, 0x55);
+    bus.write(0xFF46, 0xC0);
+    assert_eq!(0xC0, bus.read(0xFF46));
+
+    // the first cycle after the write is setup, and OAM is still reachable
+    bus.tick();
+    assert_eq!(0x00, bus.read(0xFE00));
+
+    for i in 0..0xA0 {
+        bus.tick();
+        assert_eq!(0xFF, bus.read(0xFE00));
+        assert_eq!(0x55, bus.read(0xFF80));
+        // reads from the bus being copied from see the byte being copied
+        assert_eq!(0x10 + i, bus.read(0xD123));
+        assert_eq!(0x00, bus.read(0x8000));
+    }
+    bus.tick();
+    assert!(!bus.dma.active());
+    for i in 0..0xA0 {
+        assert_eq!(0x10 + i as u8, bus.read(0xFE00 + i));
+    }
+}
+
+#[test]
+fn test_dma_from_vram_conflicts_with_vram() {
+    let mut bus = lcd_off(vec![0; 0x8000]);
+    fill_page(&mut bus, 0x8000, 0x40);
+    bus.write(0xC000, 0x99);
+    bus.write(0xFF46, 0x80);
+    bus.tick();
+    bus.tick();
+    assert_eq!(0x40, bus.read(0x9FFF));
+    assert_eq!(0x99, bus.read(0xC000));
+
+    // writes to the busy bus are dropped
+    bus.write(0x9000, 0x12);
+    for _ in 0..0xA0 {
+        bus.tick();
+    }
+    assert_eq!(0x00, bus.read(0x9000));
+    assert_eq!(0x40, bus.read(0xFE00));
+}
+
+#[test]
+fn test_dma_restart() {
+    let mut bus = lcd_off(vec![0; 0x8000]);
+    fill_page(&mut bus, 0xC000, 0x10);
+    fill_page(&mut bus, 0xC100, 0x80);
+    bus.write(0xFF46, 0xC0);
+    for _ in 0..51 {
+        bus.tick();
+    }
+
+    // the old transfer keeps OAM busy until the new one takes over
+    bus.write(0xFF46, 0xC1);
+    for _ in 0..0xA1 {
+        bus.tick();
+        assert_eq!(0xFF, bus.read(0xFE00));
+    }
+    bus.tick();
+    for i in 0..0xA0 {
+        assert_eq!(0x80u8.wrapping_add(i as u8), bus.read(0xFE00 + i));
+    }
+}
+
+#[test]
+fn test_dma_routine_in_hram() {
+    let mut rom = vec![0; 0x8000];
+    rom[0x0100..0x0108].copy_from_slice(&[
+        0x31, 0xFE, 0xFF, // LD SP,FFFE
+        0xCD, 0x80, 0xFF, // CALL FF80
+        0x18, 0xFE, // JR -2
+    ]);
+    let mut bus = memory_map(rom);
+    fill_page(&mut bus, 0xC000, 0x20);
+    let routine = [
+        0x3E, 0xC0, // LD A,C0
+        0xE0, 0x46, // LDH (46),A
+        0x3E, 0x28, // LD A,28
+        0x3D, // DEC A
+        0x20, 0xFD, // JR NZ,-3
+        0xC9, // RET
+    ];
+    for (i, byte) in routine.iter().enumerate() {
+        bus.write(0xFF80 + i as u16, *byte);
+    }
+
+    let mut cpu = new_cpu_with_bus(bus);
+    cpu.pc = 0x0100;
+    for _ in 0..200 {
+        if cpu.pc == 0x0106 {
+            break;
+        }
+        cpu.step().unwrap();
+    }
+    assert_eq!(0x0106, cpu.pc);
+    assert!(!cpu.bus.dma.active());
+    for i in 0..0xA0 {
+        assert_eq!(0x20 + i as u8, cpu.bus.read(0xFE00 + i));
+    }
+}
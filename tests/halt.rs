@@ -0,0 +1,119 @@
+mod helpers;
+
+use helpers::*;
+
+use rustyboy::interrupts::Interrupt;
+
+#[test]
+fn test_halt_waits_for_interrupt_and_dispatches() {
+    let mut cpu = cpu_with_program(&[0x76, 0x00]); // HALT, NOP
+    cpu.registers.sp = 0xFFFE;
+    cpu.ime = true;
+    cpu.bus.memory[0xFFFF] = 0x01;
+
+    assert_eq!(Ok(1), cpu.step());
+    assert!(cpu.halted);
+    for _ in 0..10 {
+        assert_eq!(Ok(1), cpu.step());
+    }
+    assert_eq!(0x0101, cpu.pc);
+
+    cpu.request_interrupt(Interrupt::VBlank);
+    assert_eq!(Ok(5), cpu.step());
+    assert!(!cpu.halted);
+    assert_eq!(0x0040, cpu.pc);
+    assert_eq!(0x01, cpu.bus.memory[0xFFFC]);
+}
+
+#[test]
+fn test_halt_wakes_without_dispatch_when_ime_clear() {
+    let mut cpu = cpu_with_program(&[0x76, 0x3C]); // HALT, INC A
+    cpu.bus.memory[0xFFFF] = 0x04;
+
+    assert_eq!(Ok(1), cpu.step());
+    assert_eq!(Ok(1), cpu.step());
+    assert!(cpu.halted);
+
+    cpu.request_interrupt(Interrupt::Timer);
+    assert_eq!(Ok(1), cpu.step());
+    assert!(!cpu.halted);
+    assert_eq!(1, cpu.registers.a);
+    assert_eq!(0x0102, cpu.pc);
+    // the interrupt is still pending since it was never serviced
+    assert_eq!(0x04, cpu.bus.memory[0xFF0F]);
+}
+
+#[test]
+fn test_halt_bug() {
+    let mut cpu = cpu_with_program(&[
+        0x76, // HALT
+        0x3E, 0x14, // LD A,14h
+    ]);
+    cpu.bus.memory[0xFFFF] = 0x01;
+    cpu.request_interrupt(Interrupt::VBlank);
+
+    assert_eq!(Ok(1), cpu.step());
+    assert!(!cpu.halted);
+
+    // 3E is read twice, so this executes LD A,3Eh and then INC D (14h)
+    assert_eq!(Ok(2), cpu.step());
+    assert_eq!(0x3E, cpu.registers.a);
+    assert_eq!(0x0102, cpu.pc);
+    assert_eq!(Ok(1), cpu.step());
+    assert_eq!(1, cpu.registers.d);
+    assert_eq!(0x0103, cpu.pc);
+}
+
+#[test]
+fn test_ei_halt_with_interrupt_pending() {
+    let mut cpu = cpu_with_program(&[
+        0xFB, // EI
+        0x76, // HALT
+        0x3C, // INC A
+    ]);
+    cpu.bus.load(0x0040, &[0x3E, 0x12, 0xD9]); // LD A,12h; RETI
+    cpu.registers.sp = 0xFFFE;
+    cpu.bus.memory[0xFFFF] = 0x01;
+    cpu.request_interrupt(Interrupt::VBlank);
+
+    assert_eq!(Ok(1), cpu.step());
+    assert_eq!(Ok(1), cpu.step());
+    assert!(!cpu.halted);
+
+    // the interrupt is taken with the HALT as the return address
+    assert_eq!(Ok(5), cpu.step());
+    assert_eq!(0x0040, cpu.pc);
+    assert_eq!([0x01, 0x01], cpu.bus.memory[0xFFFC..0xFFFE]);
+
+    // and the handler runs normally
+    assert_eq!(Ok(2), cpu.step());
+    assert_eq!(0x12, cpu.registers.a);
+    assert_eq!(0x0042, cpu.pc);
+    cpu.step().unwrap();
+    assert_eq!(0x0101, cpu.pc);
+
+    // so HALT runs again, this time halting properly
+    assert_eq!(Ok(1), cpu.step());
+    assert!(cpu.halted);
+    assert_eq!(0x0102, cpu.pc);
+}
+
+#[test]
+fn test_stop_waits_for_joypad() {
+    let mut cpu = cpu_with_program(&[0x10, 0x00, 0x3C]); // STOP, INC A
+
+    assert_eq!(Ok(1), cpu.step());
+    assert!(cpu.stopped);
+    assert_eq!(0x0102, cpu.pc);
+
+    // other interrupts don't wake it up
+    cpu.bus.memory[0xFFFF] = 0x1F;
+    cpu.request_interrupt(Interrupt::Timer);
+    assert_eq!(Ok(1), cpu.step());
+    assert!(cpu.stopped);
+
+    cpu.request_interrupt(Interrupt::Joypad);
+    assert_eq!(Ok(1), cpu.step());
+    assert!(!cpu.stopped);
+    assert_eq!(1, cpu.registers.a);
+}
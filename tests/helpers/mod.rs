@@ -1,9 +1,18 @@
-use std::{error::Error, fs::File, io::BufReader};
+#![allow(dead_code)]
+
+use std::{fs::File, io::BufReader};
 
 use num::Num;
 use serde::{Deserialize, Serialize};
-use serde_json::Deserializer;
-use subprocess::{Popen, PopenConfig, Redirection, Redirection::Pipe};
+
+use rustyboy::bus::{Bus, FlatRam, MemoryMap};
+use rustyboy::cartridge::header::{global_checksum, header_checksum, NINTENDO_LOGO};
+use rustyboy::cartridge::{Cartridge, RomOnly};
+use rustyboy::cpu::{new_cpu_with_bus, CPU};
+use rustyboy::ppu::M_CYCLES_PER_FRAME;
+
+// LD B,B, which test ROMs execute as a breakpoint once they're done.
+const BREAKPOINT_OPCODE: u8 = 0x40;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TestEntry {
@@ -14,8 +23,8 @@ pub struct TestEntry {
 }
 
 fn value_as<T: Num>(value: &str) -> T where {
-    match T::from_str_radix(value, 16) {
-        Ok(v) => return v,
+    match T::from_str_radix(value.trim_start_matches("0x"), 16) {
+        Ok(v) => v,
         Err(_) => {
             panic!("can't convert")
         }
@@ -23,15 +32,23 @@ fn value_as<T: Num>(value: &str) -> T where {
 }
 
 pub fn x_as<T: Num>(t: &TestEntry) -> T {
-    return value_as(&t.x);
+    value_as(&t.x)
 }
 
 pub fn y_as<T: Num>(t: &TestEntry) -> T {
-    return value_as(&t.y);
+    value_as(&t.y)
+}
+
+pub fn flags_as<T: Num>(t: &TestEntry) -> T {
+    value_as(&t.flags)
 }
 
 pub fn result_value<T: Num>(t: &TestEntry) -> T {
-    return value_as(&t.result.value);
+    value_as(&t.result.value)
+}
+
+pub fn result_flags<T: Num>(t: &TestEntry) -> T {
+    value_as(&t.result.flags)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -42,7 +59,7 @@ pub struct TestResult {
 
 pub fn stream_json_from_array_file<F>(filename: &str, mut f: F)
 where
-    F: FnMut(TestEntry) -> (),
+    F: FnMut(TestEntry),
 {
     let file = File::open(filename).unwrap();
     let reader = BufReader::new(file);
@@ -51,7 +68,76 @@ where
     for item in array_iter {
         match item {
             Ok(value) => f(value),
-            Err(e) => panic!("died"),
+            Err(_) => panic!("died"),
         }
     }
 }
+
+/// A CPU with `program` loaded at 0x0100 on a flat bus, ready to step from there.
+pub fn cpu_with_program(program: &[u8]) -> CPU {
+    let mut ram = FlatRam::new();
+    ram.load(0x0100, program);
+    let mut cpu = new_cpu_with_bus(ram);
+    cpu.pc = 0x0100;
+    cpu
+}
+
+/// A ROM image with a valid header for the given cartridge type and size
+/// codes. The first byte of every 16 KiB bank holds the bank number (mod 256)
+/// so tests can tell which bank is mapped in.
+pub fn build_rom(cartridge_type: u8, rom_size: u8, ram_size: u8) -> Vec<u8> {
+    let mut rom = vec![0; 0x8000 << rom_size];
+    for (bank, chunk) in rom.chunks_mut(0x4000).enumerate() {
+        chunk[0] = bank as u8;
+        chunk[1] = (bank >> 8) as u8;
+    }
+    rom[0x0104..0x0134].copy_from_slice(&NINTENDO_LOGO);
+    rom[0x0134..0x0138].copy_from_slice(b"TEST");
+    rom[0x0147] = cartridge_type;
+    rom[0x0148] = rom_size;
+    rom[0x0149] = ram_size;
+    rom[0x014D] = header_checksum(&rom);
+    let [msb, lsb] = global_checksum(&rom).to_be_bytes();
+    rom[0x014E] = msb;
+    rom[0x014F] = lsb;
+    rom
+}
+
+/// The full memory map with `rom` as a plain 32 KiB cartridge, whatever its
+/// header says.
+pub fn memory_map(rom: Vec<u8>) -> MemoryMap {
+    MemoryMap::new(Box::new(RomOnly::new(rom, 0)))
+}
+
+/// The full memory map with the mapper `rom`'s header asks for.
+pub fn cartridge_memory_map(rom: Vec<u8>) -> MemoryMap {
+    MemoryMap::new(Cartridge::from_rom(rom).unwrap().mapper)
+}
+
+/// A CPU on the full memory map with the ROM at `path` inserted and the
+/// registers as the DMG boot ROM leaves them.
+pub fn boot_rom_file(path: &str) -> CPU<MemoryMap> {
+    let rom = std::fs::read(path).unwrap();
+    let mut cpu = new_cpu_with_bus(cartridge_memory_map(rom));
+    cpu.registers.set_af(0x01B0);
+    cpu.registers.set_bc(0x0013);
+    cpu.registers.set_de(0x00D8);
+    cpu.registers.set_hl(0x014D);
+    cpu.registers.sp = 0xFFFE;
+    cpu.pc = 0x0100;
+    cpu
+}
+
+/// Steps until the next instruction is the LD B,B breakpoint, failing if it
+/// doesn't come up within `frames` frames.
+pub fn run_to_breakpoint(cpu: &mut CPU<MemoryMap>, frames: u64) {
+    let mut cycles = 0;
+    while cpu.bus.read(cpu.pc) != BREAKPOINT_OPCODE {
+        cycles += cpu.step().unwrap() as u64;
+        assert!(
+            cycles < frames * M_CYCLES_PER_FRAME,
+            "no breakpoint after {} frames",
+            frames
+        );
+    }
+}
@@ -0,0 +1,100 @@
+mod helpers;
+
+use helpers::*;
+
+use rustyboy::bus::{Bus, MemoryMap};
+use rustyboy::cartridge::RomOnly;
+use rustyboy::interrupts::Interrupt;
+
+#[test]
+fn test_dispatch_in_priority_order() {
+    let mut cpu = cpu_with_program(&[0x00, 0x00]);
+    cpu.registers.sp = 0xFFFE;
+    cpu.ime = true;
+    cpu.bus.memory[0xFFFF] = 0x1F;
+    cpu.request_interrupt(Interrupt::Joypad);
+    cpu.request_interrupt(Interrupt::Timer);
+
+    assert_eq!(Ok(5), cpu.step());
+    assert_eq!(0x0050, cpu.pc);
+    assert!(!cpu.ime);
+    assert_eq!(Interrupt::Joypad.mask(), cpu.bus.memory[0xFF0F]);
+    assert_eq!(0x01, cpu.bus.memory[0xFFFD]);
+    assert_eq!(0x00, cpu.bus.memory[0xFFFC]);
+
+    // RETI returns and re-enables interrupts straight away
+    cpu.bus.memory[0x0050] = 0xD9;
+    assert_eq!(Ok(4), cpu.step());
+    assert_eq!(0x0100, cpu.pc);
+    assert!(cpu.ime);
+    assert_eq!(Ok(5), cpu.step());
+    assert_eq!(0x0060, cpu.pc);
+    assert_eq!(0x00, cpu.bus.memory[0xFF0F]);
+}
+
+#[test]
+fn test_no_dispatch_when_disabled() {
+    let mut cpu = cpu_with_program(&[0x00, 0x00]);
+    cpu.bus.memory[0xFFFF] = 0x01;
+    cpu.request_interrupt(Interrupt::VBlank);
+
+    assert_eq!(Ok(1), cpu.step());
+    assert_eq!(0x0101, cpu.pc);
+
+    // requested but not enabled in IE
+    cpu.ime = true;
+    cpu.bus.memory[0xFFFF] = 0x02;
+    assert_eq!(Ok(1), cpu.step());
+    assert_eq!(0x0102, cpu.pc);
+}
+
+#[test]
+fn test_ei_is_delayed_by_one_instruction() {
+    let mut cpu = cpu_with_program(&[
+        0xFB, // EI
+        0x00, // NOP
+        0x00, // NOP
+    ]);
+    cpu.registers.sp = 0xFFFE;
+    cpu.bus.memory[0xFFFF] = 0x04;
+    cpu.request_interrupt(Interrupt::Timer);
+
+    assert_eq!(Ok(1), cpu.step());
+    assert!(!cpu.ime);
+    assert_eq!(Ok(1), cpu.step());
+    assert!(cpu.ime);
+    assert_eq!(0x0102, cpu.pc);
+    assert_eq!(Ok(5), cpu.step());
+    assert_eq!(0x0050, cpu.pc);
+    assert_eq!(0x02, cpu.bus.memory[0xFFFC]);
+}
+
+#[test]
+fn test_di_cancels_pending_ei() {
+    let mut cpu = cpu_with_program(&[
+        0xFB, // EI
+        0xF3, // DI
+        0x00, // NOP
+    ]);
+    cpu.bus.memory[0xFFFF] = 0x01;
+    cpu.request_interrupt(Interrupt::VBlank);
+
+    assert_eq!(Ok(3), cpu.run_for_cycles(3));
+    assert!(!cpu.ime);
+    assert_eq!(0x0103, cpu.pc);
+}
+
+#[test]
+fn test_memory_map_interrupt_registers() {
+    let mut bus = MemoryMap::new(Box::new(RomOnly::new(vec![], 0)));
+    assert_eq!(0xE0, bus.read(0xFF0F));
+
+    bus.request_interrupt(Interrupt::LcdStat);
+    bus.request_interrupt(Interrupt::Serial);
+    assert_eq!(0xEA, bus.read(0xFF0F));
+
+    bus.write(0xFF0F, 0xFF);
+    assert_eq!(0xFF, bus.read(0xFF0F));
+    bus.write(0xFF0F, 0x00);
+    assert_eq!(0xE0, bus.read(0xFF0F));
+}
@@ -0,0 +1,127 @@
+mod helpers;
+
+use helpers::*;
+
+use rustyboy::bus::Bus;
+use rustyboy::cpu::new_cpu_with_bus;
+use rustyboy::interrupts::Interrupt;
+use rustyboy::joypad::{Button, Buttons, Joypad, M_CYCLES_PER_FRAME};
+
+#[test]
+fn test_p1_select_lines() {
+    let mut joypad = Joypad::new();
+    assert_eq!(0xFF, joypad.read());
+
+    joypad.set_pressed(Button::A | Button::Right | Button::Start);
+    joypad.write(0x20);
+    assert_eq!(0xEE, joypad.read());
+    joypad.write(0x10);
+    assert_eq!(0xD6, joypad.read());
+    // with both selected the rows are combined
+    joypad.write(0x00);
+    assert_eq!(0xC6, joypad.read());
+    joypad.write(0x30);
+    assert_eq!(0xFF, joypad.read());
+}
+
+#[test]
+fn test_joypad_interrupt() {
+    let mut joypad = Joypad::new();
+    joypad.write(0x20);
+    assert_eq!(0, joypad.tick());
+
+    joypad.set_pressed(Button::Down);
+    assert_eq!(Interrupt::Joypad.mask(), joypad.tick());
+    assert_eq!(0, joypad.tick());
+
+    // buttons on the unselected row don't pull a line low
+    joypad.set_pressed(Button::Down | Button::B);
+    assert_eq!(0, joypad.tick());
+
+    // selecting a row with a button held does
+    joypad.write(0x00);
+    assert_eq!(Interrupt::Joypad.mask(), joypad.tick());
+
+    // releasing doesn't
+    joypad.set_pressed(Buttons::NONE);
+    assert_eq!(0, joypad.tick());
+}
+
+#[test]
+fn test_scheduled_input() {
+    let mut joypad = Joypad::new();
+    joypad.write(0x10);
+    joypad.schedule(20, Buttons::NONE);
+    joypad.schedule(10, Button::Start);
+    joypad.schedule(10, Button::A);
+
+    for _ in 0..9 {
+        assert_eq!(0, joypad.tick());
+    }
+    assert_eq!(Buttons::NONE, joypad.pressed());
+    assert_eq!(Interrupt::Joypad.mask(), joypad.tick());
+    assert_eq!(10, joypad.cycle());
+    // the later of two changes for the same cycle wins
+    assert_eq!(Buttons::from(Button::A), joypad.pressed());
+
+    for _ in 0..10 {
+        joypad.tick();
+    }
+    assert_eq!(Buttons::NONE, joypad.pressed());
+
+    // input by frame
+    let buttons: Buttons = [Button::Up, Button::Select].into_iter().collect();
+    joypad.schedule(2 * M_CYCLES_PER_FRAME, buttons);
+    while joypad.cycle() < 2 * M_CYCLES_PER_FRAME {
+        joypad.tick();
+    }
+    assert!(joypad.pressed().contains(Button::Select));
+    assert!(!joypad.pressed().contains(Button::Start));
+}
+
+#[test]
+fn test_joypad_wakes_stop() {
+    let mut rom = vec![0; 0x8000];
+    rom[0x0100..0x0103].copy_from_slice(&[0x10, 0x00, 0x3C]); // STOP, INC A
+    let mut bus = memory_map(rom);
+    bus.write(0xFF00, 0x10);
+    bus.joypad.schedule(100, Button::Start);
+
+    let mut cpu = new_cpu_with_bus(bus);
+    cpu.pc = 0x0100;
+    cpu.registers.a = 0;
+    cpu.step().unwrap();
+    while cpu.stopped {
+        assert!(cpu.bus.joypad.cycle() <= 100);
+        cpu.step().unwrap();
+    }
+    assert_eq!(1, cpu.registers.a);
+    assert_ne!(0, cpu.bus.read(0xFF0F) & Interrupt::Joypad.mask());
+    assert_eq!(0xD7, cpu.bus.read(0xFF00));
+}
+
+#[test]
+fn test_stop_freezes_everything_but_the_joypad() {
+    let mut rom = vec![0; 0x8000];
+    rom[0x0100..0x0103].copy_from_slice(&[0x10, 0x00, 0x3C]); // STOP, INC A
+    let mut bus = memory_map(rom);
+    bus.write(0xFF00, 0x10);
+    bus.joypad.schedule(5000, Button::A);
+
+    let mut cpu = new_cpu_with_bus(bus);
+    cpu.pc = 0x0100;
+    cpu.step().unwrap();
+    assert!(cpu.stopped);
+    let div = cpu.bus.read(0xFF04);
+    let ly = cpu.bus.read(0xFF44);
+    let dot = cpu.bus.ppu.dot();
+
+    // the step that wakes up runs INC A, which ticks everything again
+    while cpu.stopped {
+        assert_eq!(div, cpu.bus.read(0xFF04));
+        assert_eq!((ly, dot), (cpu.bus.read(0xFF44), cpu.bus.ppu.dot()));
+        cpu.step().unwrap();
+    }
+    assert!(cpu.bus.joypad.cycle() >= 5000);
+    assert_eq!(1, cpu.registers.a);
+}
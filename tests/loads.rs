@@ -0,0 +1,121 @@
+mod helpers;
+
+use helpers::*;
+
+#[test]
+fn test_load_through_register_pairs() {
+    let mut cpu = cpu_with_program(&[
+        0x0A, // LD A,(BC)
+        0x12, // LD (DE),A
+        0x22, // LD (HL+),A
+        0x32, // LD (HL-),A
+        0x2A, // LD A,(HL+)
+    ]);
+    cpu.registers.set_bc(0xC000);
+    cpu.registers.set_de(0xC001);
+    cpu.registers.set_hl(0xC010);
+    cpu.bus.memory[0xC000] = 0x5A;
+    cpu.bus.memory[0xC011] = 0x77;
+
+    assert_eq!(Ok(2), cpu.step());
+    assert_eq!(0x5A, cpu.registers.a);
+    assert_eq!(Ok(2), cpu.step());
+    assert_eq!(0x5A, cpu.bus.memory[0xC001]);
+    assert_eq!(Ok(2), cpu.step());
+    assert_eq!(0x5A, cpu.bus.memory[0xC010]);
+    assert_eq!(0xC011, cpu.registers.get_hl());
+    assert_eq!(Ok(2), cpu.step());
+    assert_eq!(0x5A, cpu.bus.memory[0xC011]);
+    assert_eq!(0xC010, cpu.registers.get_hl());
+    assert_eq!(Ok(2), cpu.step());
+    assert_eq!(0x5A, cpu.registers.a);
+    assert_eq!(0xC011, cpu.registers.get_hl());
+}
+
+#[test]
+fn test_load_hl_indirect() {
+    let mut cpu = cpu_with_program(&[
+        0x36, 0x42, // LD (HL),42h
+        0x46, // LD B,(HL)
+        0x71, // LD (HL),C
+    ]);
+    cpu.registers.set_hl(0xD000);
+    cpu.registers.c = 0x99;
+
+    assert_eq!(Ok(3), cpu.step());
+    assert_eq!(0x42, cpu.bus.memory[0xD000]);
+    assert_eq!(Ok(2), cpu.step());
+    assert_eq!(0x42, cpu.registers.b);
+    assert_eq!(Ok(2), cpu.step());
+    assert_eq!(0x99, cpu.bus.memory[0xD000]);
+}
+
+#[test]
+fn test_load_absolute_and_high() {
+    let mut cpu = cpu_with_program(&[
+        0xEA, 0x00, 0xC1, // LD (C100),A
+        0xE0, 0x80, // LDH (80),A
+        0xE2, // LD (C),A
+        0xF0, 0x81, // LDH A,(81)
+        0xFA, 0x02, 0xC1, // LD A,(C102)
+        0xF2, // LD A,(C)
+    ]);
+    cpu.registers.a = 0x12;
+    cpu.registers.c = 0x90;
+    cpu.bus.memory[0xFF81] = 0x34;
+    cpu.bus.memory[0xC102] = 0x56;
+    cpu.bus.memory[0xFF90] = 0x78;
+
+    assert_eq!(Ok(4), cpu.step());
+    assert_eq!(0x12, cpu.bus.memory[0xC100]);
+    assert_eq!(Ok(3), cpu.step());
+    assert_eq!(0x12, cpu.bus.memory[0xFF80]);
+    assert_eq!(Ok(2), cpu.step());
+    assert_eq!(0x12, cpu.bus.memory[0xFF90]);
+    assert_eq!(Ok(3), cpu.step());
+    assert_eq!(0x34, cpu.registers.a);
+    assert_eq!(Ok(4), cpu.step());
+    assert_eq!(0x56, cpu.registers.a);
+    assert_eq!(Ok(2), cpu.step());
+    assert_eq!(0x12, cpu.registers.a);
+}
+
+#[test]
+fn test_load_nn_sp() {
+    let mut cpu = cpu_with_program(&[0x08, 0x00, 0xC0]); // LD (C000),SP
+    cpu.registers.sp = 0xBEEF;
+
+    assert_eq!(Ok(5), cpu.step());
+    assert_eq!(0xEF, cpu.bus.memory[0xC000]);
+    assert_eq!(0xBE, cpu.bus.memory[0xC001]);
+}
+
+#[test]
+fn test_sp_relative() {
+    let mut cpu = cpu_with_program(&[
+        0xF8, 0x01, // LD HL,SP+1
+        0xF8, 0xFF, // LD HL,SP-1
+        0xE8, 0x08, // ADD SP,8
+    ]);
+    cpu.registers.sp = 0x00FF;
+    cpu.registers.f.zero = true;
+    cpu.registers.f.subtract = true;
+
+    assert_eq!(Ok(3), cpu.step());
+    assert_eq!(0x0100, cpu.registers.get_hl());
+    assert!(!cpu.registers.f.zero);
+    assert!(!cpu.registers.f.subtract);
+    assert!(cpu.registers.f.half_carry);
+    assert!(cpu.registers.f.carry);
+
+    assert_eq!(Ok(3), cpu.step());
+    assert_eq!(0x00FE, cpu.registers.get_hl());
+    assert!(cpu.registers.f.half_carry);
+    assert!(cpu.registers.f.carry);
+
+    cpu.registers.sp = 0xFFF0;
+    assert_eq!(Ok(4), cpu.step());
+    assert_eq!(0xFFF8, cpu.registers.sp);
+    assert!(!cpu.registers.f.half_carry);
+    assert!(!cpu.registers.f.carry);
+}
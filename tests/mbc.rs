@@ -0,0 +1,571 @@
+mod helpers;
+
+use helpers::*;
+
+use rustyboy::bus::{Bus, MemoryMap};
+use rustyboy::cartridge::header::NINTENDO_LOGO;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use rustyboy::cartridge::camera::{SENSOR_HEIGHT, SENSOR_WIDTH};
+use rustyboy::cartridge::{Cartridge, InfraredPort, Mbc1, VirtualClock};
+
+#[test]
+fn test_mbc1_rom_banking() {
+    let mut bus = cartridge_memory_map(build_rom(0x01, 0x04, 0x00));
+    assert_eq!(0, bus.read(0x0000));
+    assert_eq!(1, bus.read(0x4000));
+
+    bus.write(0x2000, 0x05);
+    assert_eq!(5, bus.read(0x4000));
+    bus.write(0x3FFF, 0x1F);
+    assert_eq!(0x1F, bus.read(0x4000));
+
+    // bank 0 can't be selected at 0x4000
+    bus.write(0x2000, 0x00);
+    assert_eq!(1, bus.read(0x4000));
+
+    // only as many bits as the ROM needs are used
+    bus.write(0x2000, 0x25);
+    assert_eq!(5, bus.read(0x4000));
+}
+
+#[test]
+fn test_mbc1_large_rom_and_bank_quirk() {
+    let mut bus = cartridge_memory_map(build_rom(0x01, 0x06, 0x00));
+
+    bus.write(0x4000, 0x01);
+    bus.write(0x2000, 0x02);
+    assert_eq!(0x22, bus.read(0x4000));
+
+    // 0x20, 0x40 and 0x60 become 0x21, 0x41 and 0x61
+    for upper in 1..=3u8 {
+        bus.write(0x4000, upper);
+        bus.write(0x2000, 0x00);
+        assert_eq!(upper << 5 | 1, bus.read(0x4000));
+    }
+
+    // in mode 1 bank2 also applies to 0x0000-0x3FFF
+    bus.write(0x4000, 0x02);
+    assert_eq!(0, bus.read(0x0000));
+    bus.write(0x6000, 0x01);
+    assert_eq!(0x40, bus.read(0x0000));
+    bus.write(0x6000, 0x00);
+    assert_eq!(0, bus.read(0x0000));
+}
+
+#[test]
+fn test_mbc1_ram() {
+    let mut bus = cartridge_memory_map(build_rom(0x03, 0x04, 0x03));
+
+    // disabled until 0x0A is written to 0000-1FFF
+    bus.write(0xA000, 0x12);
+    assert_eq!(0xFF, bus.read(0xA000));
+    bus.write(0x0000, 0x0A);
+    bus.write(0xA000, 0x12);
+    assert_eq!(0x12, bus.read(0xA000));
+
+    // RAM banks only switch in mode 1
+    bus.write(0x4000, 0x02);
+    assert_eq!(0x12, bus.read(0xA000));
+    bus.write(0x6000, 0x01);
+    assert_eq!(0x00, bus.read(0xA000));
+    bus.write(0xA000, 0x34);
+    bus.write(0x4000, 0x00);
+    assert_eq!(0x12, bus.read(0xA000));
+    bus.write(0x4000, 0x02);
+    assert_eq!(0x34, bus.read(0xA000));
+
+    bus.write(0x1000, 0x00);
+    assert_eq!(0xFF, bus.read(0xA000));
+}
+
+#[test]
+fn test_mbc1_multicart() {
+    let mut rom = build_rom(0x01, 0x05, 0x00);
+    assert!(!Mbc1::new(rom.clone(), 0).is_multicart());
+    rom[0x40104..0x40134].copy_from_slice(&NINTENDO_LOGO);
+    assert!(Mbc1::new(rom.clone(), 0).is_multicart());
+
+    let mut bus = cartridge_memory_map(rom);
+    bus.write(0x4000, 0x01);
+    bus.write(0x2000, 0x02);
+    assert_eq!(0x12, bus.read(0x4000));
+
+    // bank1's top bit isn't connected
+    bus.write(0x2000, 0x13);
+    assert_eq!(0x13, bus.read(0x4000));
+    bus.write(0x2000, 0x10);
+    assert_eq!(0x10, bus.read(0x4000));
+
+    bus.write(0x6000, 0x01);
+    bus.write(0x4000, 0x03);
+    assert_eq!(0x30, bus.read(0x0000));
+}
+
+fn mbc3_with_clock(clock: &VirtualClock) -> MemoryMap {
+    let rom = build_rom(0x10, 0x04, 0x03);
+    let cartridge = Cartridge::from_rom_with_clock(rom, Box::new(clock.clone())).unwrap();
+    MemoryMap::new(cartridge.mapper)
+}
+
+fn latch(bus: &mut MemoryMap) {
+    bus.write(0x6000, 0x00);
+    bus.write(0x6000, 0x01);
+}
+
+fn read_rtc(bus: &mut MemoryMap) -> [u8; 5] {
+    let mut registers = [0; 5];
+    for (i, register) in (0x08..=0x0C).enumerate() {
+        bus.write(0x4000, register);
+        registers[i] = bus.read(0xA000);
+    }
+    registers
+}
+
+#[test]
+fn test_mbc3_banking() {
+    let mut bus = cartridge_memory_map(build_rom(0x13, 0x06, 0x03));
+    bus.write(0x2000, 0x7F);
+    assert_eq!(0x7F, bus.read(0x4000));
+    bus.write(0x2000, 0x00);
+    assert_eq!(1, bus.read(0x4000));
+
+    bus.write(0x0000, 0x0A);
+    for bank in 0..4 {
+        bus.write(0x4000, bank);
+        bus.write(0xA000, 0x10 + bank);
+    }
+    for bank in 0..4 {
+        bus.write(0x4000, bank);
+        assert_eq!(0x10 + bank, bus.read(0xA000));
+    }
+}
+
+#[test]
+fn test_mbc3_rtc_latch() {
+    let clock = VirtualClock::new(1_000_000);
+    let mut bus = mbc3_with_clock(&clock);
+    bus.write(0x0000, 0x0A);
+
+    clock.advance(3 * 86400 + 2 * 3600 + 5 * 60 + 7);
+    // nothing is visible until latched
+    assert_eq!([0, 0, 0, 0, 0], read_rtc(&mut bus));
+    latch(&mut bus);
+    assert_eq!([7, 5, 2, 3, 0], read_rtc(&mut bus));
+
+    // the latched values stay put while the clock runs
+    clock.advance(60);
+    assert_eq!([7, 5, 2, 3, 0], read_rtc(&mut bus));
+    // writing 0x01 without a 0x00 first doesn't latch
+    bus.write(0x6000, 0x01);
+    assert_eq!([7, 5, 2, 3, 0], read_rtc(&mut bus));
+    latch(&mut bus);
+    assert_eq!([7, 6, 2, 3, 0], read_rtc(&mut bus));
+}
+
+#[test]
+fn test_mbc3_rtc_halt_and_day_carry() {
+    let clock = VirtualClock::new(0);
+    let mut bus = mbc3_with_clock(&clock);
+    bus.write(0x0000, 0x0A);
+
+    // halted clocks don't move
+    bus.write(0x4000, 0x0C);
+    bus.write(0xA000, 0x40);
+    clock.advance(1000);
+    latch(&mut bus);
+    assert_eq!([0, 0, 0, 0, 0x40], read_rtc(&mut bus));
+
+    // day 511, 23:59:59
+    for (register, value) in [
+        (0x08, 59),
+        (0x09, 59),
+        (0x0A, 23),
+        (0x0B, 0xFF),
+        (0x0C, 0x01),
+    ] {
+        bus.write(0x4000, register);
+        bus.write(0xA000, value);
+    }
+    clock.advance(2);
+    latch(&mut bus);
+    assert_eq!([1, 0, 0, 0, 0x80], read_rtc(&mut bus));
+
+    // the carry stays set until cleared
+    clock.advance(86400);
+    latch(&mut bus);
+    assert_eq!([1, 0, 0, 1, 0x80], read_rtc(&mut bus));
+}
+
+#[test]
+fn test_mbc3_rtc_out_of_range_values() {
+    let clock = VirtualClock::new(0);
+    let mut bus = mbc3_with_clock(&clock);
+    bus.write(0x0000, 0x0A);
+
+    // seconds past 59 count up to 63 and wrap without a carry
+    bus.write(0x4000, 0x08);
+    bus.write(0xA000, 62);
+    clock.advance(3);
+    latch(&mut bus);
+    assert_eq!([1, 0, 0, 0, 0], read_rtc(&mut bus));
+}
+
+#[test]
+fn test_mbc3_save_data_includes_rtc() {
+    let clock = VirtualClock::new(5000);
+    let mut bus = mbc3_with_clock(&clock);
+    bus.write(0x0000, 0x0A);
+    bus.write(0xA000, 0x42);
+    clock.advance(90);
+    let save = bus.cartridge.save_data();
+    assert_eq!(0x8000 + 48, save.len());
+    assert_eq!(30, save[0x8000]);
+    assert_eq!(1, save[0x8004]);
+
+    // a day later the restored clock has caught up
+    clock.advance(86400);
+    let mut restored = mbc3_with_clock(&clock);
+    restored.cartridge.load_save_data(&save);
+    restored.write(0x0000, 0x0A);
+    assert_eq!(0x42, restored.read(0xA000));
+    latch(&mut restored);
+    assert_eq!([30, 1, 0, 1, 0], read_rtc(&mut restored));
+}
+
+#[test]
+fn test_mbc2() {
+    let mut bus = cartridge_memory_map(build_rom(0x06, 0x03, 0x00));
+
+    // address bit 8 set selects the ROM bank
+    bus.write(0x2100, 0x05);
+    assert_eq!(5, bus.read(0x4000));
+    bus.write(0x0100, 0x00);
+    assert_eq!(1, bus.read(0x4000));
+    // and clear enables RAM, anywhere in 0000-3FFF
+    bus.write(0x2000, 0x0A);
+    assert_eq!(1, bus.read(0x4000));
+
+    // four bits per byte, repeated every 512 bytes
+    bus.write(0xA000, 0x5C);
+    assert_eq!(0xFC, bus.read(0xA000));
+    assert_eq!(0xFC, bus.read(0xA200));
+    assert_eq!(0xFC, bus.read(0xBE00));
+
+    bus.write(0x0000, 0x00);
+    assert_eq!(0xFF, bus.read(0xA000));
+}
+
+#[test]
+fn test_mbc5_banking() {
+    let mut bus = cartridge_memory_map(build_rom(0x1B, 0x08, 0x04));
+
+    bus.write(0x2000, 0x00);
+    assert_eq!(0, bus.read(0x4000));
+    bus.write(0x2000, 0x34);
+    bus.write(0x3000, 0x01);
+    assert_eq!(0x34, bus.read(0x4000));
+    assert_eq!(0x01, bus.read(0x4001));
+
+    bus.write(0x0000, 0x0A);
+    for bank in 0..16 {
+        bus.write(0x4000, bank);
+        bus.write(0xA000, bank);
+    }
+    for bank in 0..16 {
+        bus.write(0x4000, bank);
+        assert_eq!(bank, bus.read(0xA000));
+    }
+}
+
+#[test]
+fn test_mbc5_rumble() {
+    let mut cartridge = Cartridge::from_rom(build_rom(0x1E, 0x02, 0x03)).unwrap();
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let recorded = events.clone();
+    cartridge.on_rumble(move |on| recorded.borrow_mut().push(on));
+    let mut bus = MemoryMap::new(cartridge.mapper);
+
+    bus.write(0x0000, 0x0A);
+    bus.write(0x4000, 0x09);
+    bus.write(0x4000, 0x0A);
+    bus.write(0x4000, 0x01);
+    assert_eq!(vec![true, false], *events.borrow());
+
+    // the motor bit isn't part of the RAM bank
+    bus.write(0xA000, 0x77);
+    bus.write(0x4000, 0x09);
+    assert_eq!(0x77, bus.read(0xA000));
+}
+
+#[test]
+fn test_mmm01_menu_then_game() {
+    let mut bus = cartridge_memory_map(build_rom(0x0B, 0x04, 0x00));
+    // the menu in the last two banks is mapped in at reset
+    assert_eq!(30, bus.read(0x0000));
+    assert_eq!(31, bus.read(0x4000));
+
+    // pick the game at banks 8-15: bank 8, with ROM bank bits 3-4 fixed
+    bus.write(0x2000, 0x08);
+    bus.write(0x6000, 0x30);
+    bus.write(0x0000, 0x40);
+    assert_eq!(8, bus.read(0x0000));
+    assert_eq!(9, bus.read(0x4000));
+
+    // the game can only switch within its own eight banks
+    bus.write(0x2000, 0x03);
+    assert_eq!(11, bus.read(0x4000));
+    bus.write(0x2000, 0x1F);
+    assert_eq!(15, bus.read(0x4000));
+    assert_eq!(8, bus.read(0x0000));
+}
+
+#[test]
+fn test_mbc6_rom_and_flash() {
+    let mut bus = cartridge_memory_map(build_rom(0x20, 0x04, 0x03));
+
+    // ROM is switched in 8 KiB halves
+    bus.write(0x2000, 0x04);
+    bus.write(0x3000, 0x06);
+    assert_eq!(2, bus.read(0x4000));
+    assert_eq!(3, bus.read(0x6000));
+
+    // and so is flash, which starts out erased
+    bus.write(0x0C00, 0x01);
+    bus.write(0x1000, 0x01);
+    bus.write(0x2800, 0x08);
+    bus.write(0x2000, 0x01);
+    assert_eq!(0xFF, bus.read(0x4000));
+
+    for value in [0xAA, 0x55, 0xA0] {
+        bus.write(0x5555, value);
+    }
+    bus.write(0x4010, 0x3C);
+    assert_eq!(0x3C, bus.read(0x4010));
+    // programming can't set bits
+    for value in [0xAA, 0x55, 0xA0] {
+        bus.write(0x4000, value);
+    }
+    bus.write(0x4010, 0xC3);
+    assert_eq!(0x00, bus.read(0x4010));
+
+    for value in [0xAA, 0x55, 0x80, 0xAA, 0x55, 0x30] {
+        bus.write(0x4000, value);
+    }
+    assert_eq!(0xFF, bus.read(0x4010));
+
+    // switching back to ROM
+    bus.write(0x2800, 0x00);
+    bus.write(0x2000, 0x04);
+    assert_eq!(2, bus.read(0x4000));
+}
+
+// Start bit and opcode, to be followed by an 8-bit address.
+const EEPROM_READ: u16 = 0b110 << 8;
+const EEPROM_WRITE: u16 = 0b101 << 8;
+const EEPROM_WRITE_ENABLE: u16 = 0b100 << 8 | 0b1100_0000;
+
+fn eeprom_clock(bus: &mut MemoryMap, bit: bool) -> bool {
+    let di = if bit { 0x02 } else { 0x00 };
+    bus.write(0xA080, 0x80 | di);
+    bus.write(0xA080, 0xC0 | di);
+    bus.read(0xA080) & 0x01 != 0
+}
+
+fn eeprom_send(bus: &mut MemoryMap, bits: u16, count: u8) {
+    for i in (0..count).rev() {
+        eeprom_clock(bus, bits >> i & 1 != 0);
+    }
+}
+
+// Deselects the chip first, which cancels anything in progress.
+fn eeprom_command(bus: &mut MemoryMap, command: u16) {
+    bus.write(0xA080, 0x00);
+    bus.write(0xA080, 0x80);
+    eeprom_send(bus, command, 11);
+}
+
+#[test]
+fn test_mbc7_accelerometer() {
+    let mut cartridge = Cartridge::from_rom(build_rom(0x22, 0x04, 0x00)).unwrap();
+    let tilt = Rc::new(Cell::new((0.0f32, 0.0f32)));
+    let sensor = tilt.clone();
+    cartridge.connect_accelerometer(move || sensor.get());
+    let mut bus = MemoryMap::new(cartridge.mapper);
+
+    // both enables are needed
+    bus.write(0x0000, 0x0A);
+    assert_eq!(0xFF, bus.read(0xA020));
+    bus.write(0x4000, 0x40);
+
+    bus.write(0xA000, 0x55);
+    assert_eq!(0x8000, read_word(&mut bus, 0xA020));
+    bus.write(0xA010, 0xAA);
+    assert_eq!(0x81D0, read_word(&mut bus, 0xA020));
+    assert_eq!(0x81D0, read_word(&mut bus, 0xA040));
+
+    // nothing changes until the next latch
+    tilt.set((1.0, -1.0));
+    assert_eq!(0x81D0, read_word(&mut bus, 0xA020));
+    bus.write(0xA000, 0x55);
+    bus.write(0xA010, 0xAA);
+    assert_eq!(0x8240, read_word(&mut bus, 0xA020));
+    assert_eq!(0x8160, read_word(&mut bus, 0xA040));
+}
+
+fn read_word(bus: &mut MemoryMap, address: u16) -> u16 {
+    u16::from_le_bytes([bus.read(address), bus.read(address + 0x10)])
+}
+
+#[test]
+fn test_mbc7_eeprom() {
+    let mut bus = cartridge_memory_map(build_rom(0x22, 0x04, 0x00));
+    bus.write(0x0000, 0x0A);
+    bus.write(0x4000, 0x40);
+
+    // writes are ignored until enabled
+    eeprom_command(&mut bus, EEPROM_WRITE | 5);
+    eeprom_send(&mut bus, 0x1234, 16);
+    eeprom_command(&mut bus, EEPROM_WRITE_ENABLE);
+    eeprom_command(&mut bus, EEPROM_WRITE | 5);
+    eeprom_send(&mut bus, 0xBEEF, 16);
+
+    eeprom_command(&mut bus, EEPROM_READ | 5);
+    let word = (0..16).fold(0u16, |word, _| {
+        word << 1 | eeprom_clock(&mut bus, false) as u16
+    });
+    assert_eq!(0xBEEF, word);
+
+    let save = bus.cartridge.save_data();
+    assert_eq!(256, save.len());
+    assert_eq!([0xEF, 0xBE], save[10..12]);
+}
+
+struct Led {
+    on: Rc<Cell<bool>>,
+    light: bool,
+}
+
+impl InfraredPort for Led {
+    fn set_led(&mut self, on: bool) {
+        self.on.set(on);
+    }
+
+    fn light_detected(&mut self) -> bool {
+        self.light
+    }
+}
+
+#[test]
+fn test_huc1_infrared() {
+    let mut cartridge = Cartridge::from_rom(build_rom(0xFF, 0x04, 0x03)).unwrap();
+    let led = Rc::new(Cell::new(false));
+    cartridge.connect_infrared(Led {
+        on: led.clone(),
+        light: true,
+    });
+    let mut bus = MemoryMap::new(cartridge.mapper);
+
+    bus.write(0xA000, 0x12);
+    assert_eq!(0x12, bus.read(0xA000));
+
+    bus.write(0x0000, 0x0E);
+    assert_eq!(0xC1, bus.read(0xA000));
+    bus.write(0xA000, 0x01);
+    assert!(led.get());
+    bus.write(0xA000, 0x00);
+    assert!(!led.get());
+
+    bus.write(0x0000, 0x00);
+    assert_eq!(0x12, bus.read(0xA000));
+}
+
+#[test]
+fn test_huc3_clock() {
+    let clock = VirtualClock::new(0);
+    let rom = build_rom(0xFE, 0x04, 0x03);
+    let cartridge = Cartridge::from_rom_with_clock(rom, Box::new(clock.clone())).unwrap();
+    let mut bus = MemoryMap::new(cartridge.mapper);
+
+    let command = |bus: &mut MemoryMap, value: u8| {
+        bus.write(0x0000, 0x0B);
+        bus.write(0xA000, value);
+        bus.write(0x0000, 0x0C);
+        bus.read(0xA000)
+    };
+
+    // two days, three hours and five minutes
+    clock.advance(2 * 86400 + 3 * 3600 + 5 * 60 + 30);
+    command(&mut bus, 0x60);
+    command(&mut bus, 0x40);
+    command(&mut bus, 0x50);
+    let time = (0..6).fold(0u32, |time, i| {
+        time | ((command(&mut bus, 0x10) & 0x0F) as u32) << (i * 4)
+    });
+    assert_eq!(185, time & 0xFFF);
+    assert_eq!(2, time >> 12);
+
+    // set it to day 7, 00:01
+    command(&mut bus, 0x40);
+    for nibble in [1, 0, 0, 7, 0, 0] {
+        command(&mut bus, 0x30 | nibble);
+    }
+    command(&mut bus, 0x61);
+    clock.advance(60);
+    command(&mut bus, 0x60);
+    command(&mut bus, 0x40);
+    assert_eq!(0x12, command(&mut bus, 0x10));
+    command(&mut bus, 0x43);
+    assert_eq!(0x17, command(&mut bus, 0x10));
+}
+
+#[test]
+fn test_huc3_save_with_a_huge_counter() {
+    let clock = VirtualClock::new(120);
+    let rom = build_rom(0xFE, 0x04, 0x03);
+    let mut mapper = Cartridge::from_rom_with_clock(rom, Box::new(clock.clone()))
+        .unwrap()
+        .mapper;
+
+    // a corrupt counter just wraps around as time passes
+    let mut save = vec![0; 0x8000];
+    save.extend_from_slice(&(u64::MAX - 59).to_le_bytes());
+    save.extend_from_slice(&0u64.to_le_bytes());
+    mapper.load_save_data(&save);
+    let counter = &mapper.save_data()[0x8000..0x8008];
+    assert_eq!(60, u64::from_le_bytes(counter.try_into().unwrap()));
+}
+
+#[test]
+fn test_pocket_camera_capture() {
+    let mut cartridge = Cartridge::from_rom(build_rom(0xFC, 0x04, 0x04)).unwrap();
+    // left half black, right half white
+    cartridge.connect_image_sensor(|| {
+        (0..SENSOR_WIDTH * SENSOR_HEIGHT)
+            .map(|i| {
+                if i % SENSOR_WIDTH < SENSOR_WIDTH / 2 {
+                    0x00
+                } else {
+                    0xFF
+                }
+            })
+            .collect()
+    });
+    let mut bus = MemoryMap::new(cartridge.mapper);
+
+    bus.write(0x4000, 0x10);
+    for cell in 0..16 {
+        bus.write(0xA006 + cell * 3, 0x40);
+        bus.write(0xA007 + cell * 3, 0x80);
+        bus.write(0xA008 + cell * 3, 0xC0);
+    }
+    bus.write(0xA000, 0x01);
+    assert_eq!(0x00, bus.read(0xA000));
+
+    bus.write(0x4000, 0x00);
+    // the first tile is black, the last white
+    assert_eq!([0xFF, 0xFF], [bus.read(0xA100), bus.read(0xA101)]);
+    let last = 0xA100 + (16 * 14 - 1) * 16;
+    assert_eq!([0x00, 0x00], [bus.read(last), bus.read(last + 1)]);
+}
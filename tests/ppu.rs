@@ -0,0 +1,621 @@
+mod helpers;
+
+use helpers::*;
+
+use rustyboy::bus::{Bus, MemoryMap};
+use rustyboy::interrupts::Interrupt;
+use std::cell::Cell;
+use std::fs::File;
+use std::rc::Rc;
+
+use rustyboy::ppu::{
+    FifoRenderer, Mode, Ppu, PpuState, Renderer, ScanlineRenderer, SCREEN_HEIGHT, SCREEN_WIDTH,
+};
+
+fn run_dots(ppu: &mut Ppu, dots: u32) -> u8 {
+    (0..dots).fold(0, |interrupts, _| interrupts | ppu.tick())
+}
+
+#[test]
+fn test_mode_timings() {
+    let mut ppu = Ppu::new();
+    assert_eq!((0, Mode::OamScan), (ppu.ly(), ppu.mode()));
+    run_dots(&mut ppu, 79);
+    assert_eq!(Mode::OamScan, ppu.mode());
+    run_dots(&mut ppu, 1);
+    assert_eq!(Mode::PixelTransfer, ppu.mode());
+    run_dots(&mut ppu, 172);
+    assert_eq!(Mode::HBlank, ppu.mode());
+    run_dots(&mut ppu, 204);
+    assert_eq!((1, Mode::OamScan), (ppu.ly(), ppu.mode()));
+
+    let interrupts = run_dots(&mut ppu, 143 * 456 - 1);
+    assert_eq!(0, interrupts);
+    assert_eq!((143, Mode::HBlank), (ppu.ly(), ppu.mode()));
+    assert_eq!(Interrupt::VBlank.mask(), run_dots(&mut ppu, 1));
+    assert_eq!((144, Mode::VBlank), (ppu.ly(), ppu.mode()));
+    assert_eq!(1, ppu.frame_count());
+
+    run_dots(&mut ppu, 9 * 456);
+    assert_eq!((153, Mode::VBlank), (ppu.ly(), ppu.mode()));
+    run_dots(&mut ppu, 455);
+    assert_eq!((0, Mode::VBlank), (ppu.ly(), ppu.mode()));
+    run_dots(&mut ppu, 1);
+    assert_eq!((0, Mode::OamScan), (ppu.ly(), ppu.mode()));
+}
+
+#[test]
+fn test_stat_register_and_interrupts() {
+    let mut bus = memory_map(vec![0; 0x8000]);
+    assert_eq!(0x86, bus.read(0xFF41));
+
+    bus.write(0xFF45, 2);
+    bus.write(0xFF41, 0xFF);
+    assert_eq!(0xFA, bus.read(0xFF41));
+
+    // LYC=LY on line 2, then HBlank on the same line
+    for _ in 0..(2 * 456 / 4) {
+        bus.tick();
+    }
+    assert_eq!(2, bus.read(0xFF44));
+    assert_ne!(0, bus.read(0xFF0F) & Interrupt::LcdStat.mask());
+    assert_eq!(0x04, bus.read(0xFF41) & 0x04);
+
+    // LY can't be written
+    bus.write(0xFF44, 0x10);
+    assert_eq!(2, bus.read(0xFF44));
+}
+
+#[test]
+fn test_lcd_off() {
+    let mut bus = memory_map(vec![0; 0x8000]);
+    for _ in 0..1000 {
+        bus.tick();
+    }
+    bus.write(0xFF40, 0x11);
+    assert_eq!(0, bus.read(0xFF44));
+    assert_eq!(0x80, bus.read(0xFF41));
+    for _ in 0..1000 {
+        bus.tick();
+    }
+    assert_eq!(0, bus.read(0xFF44));
+
+    bus.write(0xFF40, 0x91);
+    for _ in 0..(456 / 4) {
+        bus.tick();
+    }
+    assert_eq!(1, bus.read(0xFF44));
+}
+
+fn stat_interrupts(ppu: &mut Ppu, dots: u32) -> usize {
+    (0..dots)
+        .filter(|_| ppu.tick() & Interrupt::LcdStat.mask() != 0)
+        .count()
+}
+
+#[test]
+fn test_stat_interrupt_line_is_shared() {
+    let mut ppu = Ppu::new();
+    ppu.write(0xFF45, 0xFF);
+    run_dots(&mut ppu, 144 * 456);
+    ppu.write(0xFF41, 0x28);
+    ppu.tick();
+
+    // HBlank runs straight into OAM scan, so with both enabled only the
+    // first OAM scan of the frame gets its own interrupt
+    assert_eq!(145, stat_interrupts(&mut ppu, 154 * 456 - 1));
+}
+
+#[test]
+fn test_ly_wraps_early_on_line_153() {
+    let mut ppu = Ppu::new();
+    ppu.write(0xFF45, 0);
+    run_dots(&mut ppu, 456 + 100);
+    ppu.write(0xFF41, 0x40);
+    assert_eq!(0, stat_interrupts(&mut ppu, 152 * 456 - 100));
+    assert_eq!(153, ppu.ly());
+
+    assert_eq!(0, stat_interrupts(&mut ppu, 3));
+    assert_eq!(1, stat_interrupts(&mut ppu, 1));
+    assert_eq!(0, ppu.ly());
+    assert_eq!(0x04, ppu.read(0xFF41) & 0x04);
+
+    // the line stays high into line 0, so there's no second interrupt
+    assert_eq!(0, stat_interrupts(&mut ppu, 452 + 456));
+    assert_eq!(1, ppu.ly());
+}
+
+#[test]
+fn test_first_frame_after_lcd_on_is_blank() {
+    let mut bus = memory_map(vec![0; 0x8000]);
+    load_tile(&mut bus);
+    bus.write(0x9800, 0x01);
+    bus.write(0xFF47, 0xE4);
+    run_frame(&mut bus);
+    assert_eq!(3, bus.ppu.framebuffer()[0]);
+
+    bus.write(0xFF40, 0x11);
+    assert!(bus.ppu.framebuffer().iter().all(|&shade| shade == 0));
+
+    // line 0 starts in HBlank rather than OAM scan
+    bus.write(0xFF40, 0x91);
+    assert_eq!(Mode::HBlank, bus.ppu.mode());
+    run_frame(&mut bus);
+    assert_eq!(0, bus.ppu.framebuffer()[0]);
+    run_frame(&mut bus);
+    assert_eq!(3, bus.ppu.framebuffer()[0]);
+}
+
+#[test]
+fn test_vram_and_oam_lockout() {
+    let mut ppu = Ppu::new();
+    ppu.state.vram[0] = 0x12;
+    ppu.state.oam[0] = 0x34;
+
+    // OAM scan locks OAM
+    assert_eq!(0x12, ppu.read(0x8000));
+    assert_eq!(0xFF, ppu.read(0xFE00));
+    ppu.write(0xFE00, 0x00);
+    assert_eq!(0x34, ppu.state.oam[0]);
+
+    // pixel transfer locks both
+    run_dots(&mut ppu, 80);
+    assert_eq!(0xFF, ppu.read(0x8000));
+    assert_eq!(0xFF, ppu.read(0xFE00));
+    ppu.write(0x8000, 0x00);
+    assert_eq!(0x12, ppu.state.vram[0]);
+
+    // with the LCD off everything is accessible
+    ppu.write(0xFF40, 0x11);
+    assert_eq!(0x12, ppu.read(0x8000));
+    assert_eq!(0x34, ppu.read(0xFE00));
+    ppu.write(0xFF40, 0x91);
+
+    run_dots(&mut ppu, 80 + 172);
+    assert_eq!(Mode::HBlank, ppu.mode());
+    ppu.write(0x8000, 0x56);
+    ppu.write(0xFE00, 0x78);
+    assert_eq!(0x56, ppu.read(0x8000));
+    assert_eq!(0x78, ppu.read(0xFE00));
+}
+
+#[test]
+fn test_stat_write_bug() {
+    let mut ppu = Ppu::new();
+    ppu.write(0xFF45, 0xFF);
+    // OAM scan isn't one of the modes it fires in
+    run_dots(&mut ppu, 10);
+    assert_eq!(Mode::OamScan, ppu.mode());
+    ppu.write(0xFF41, 0x00);
+    assert_eq!(0, stat_interrupts(&mut ppu, 1));
+
+    run_dots(&mut ppu, 79);
+    assert_eq!(Mode::PixelTransfer, ppu.mode());
+    ppu.write(0xFF41, 0x00);
+    assert_eq!(0, stat_interrupts(&mut ppu, 1));
+
+    // any write during HBlank interrupts, even one disabling every source
+    run_dots(&mut ppu, 172);
+    assert_eq!(Mode::HBlank, ppu.mode());
+    ppu.write(0xFF41, 0x00);
+    assert_eq!(1, stat_interrupts(&mut ppu, 1));
+
+    // unless the line is already high
+    ppu.write(0xFF41, 0x08);
+    assert_eq!(1, stat_interrupts(&mut ppu, 1));
+    ppu.write(0xFF41, 0x08);
+    assert_eq!(0, stat_interrupts(&mut ppu, 1));
+}
+
+// Tile 1 has colour 3 in its left column and colour 1 everywhere else.
+fn load_tile(bus: &mut MemoryMap) {
+    for row in 0..8 {
+        bus.write(0x8010 + row * 2, 0xFF);
+        bus.write(0x8011 + row * 2, 0x80);
+    }
+}
+
+fn run_frame(bus: &mut MemoryMap) {
+    for _ in 0..(154 * 456 / 4) {
+        bus.tick();
+    }
+}
+
+#[test]
+fn test_background() {
+    let mut bus = memory_map(vec![0; 0x8000]);
+    load_tile(&mut bus);
+    bus.write(0x9800, 0x01);
+    bus.write(0xFF47, 0xE4);
+    run_frame(&mut bus);
+
+    let frame = bus.ppu.framebuffer();
+    assert_eq!([3, 1, 1, 1, 1, 1, 1, 1, 0], frame[0..9]);
+    assert_eq!(3, frame[7 * SCREEN_WIDTH]);
+    assert_eq!(0, frame[8 * SCREEN_WIDTH]);
+
+    // scrolling moves the tile up and left
+    bus.write(0xFF43, 0x01);
+    bus.write(0xFF42, 0x04);
+    run_frame(&mut bus);
+    let frame = bus.ppu.framebuffer();
+    assert_eq!([1, 1, 1, 1, 1, 1, 1, 0], frame[0..8]);
+    assert_eq!(1, frame[3 * SCREEN_WIDTH]);
+    assert_eq!(0, frame[4 * SCREEN_WIDTH]);
+
+    // and the map wraps around
+    bus.write(0xFF43, 0xFC);
+    bus.write(0xFF42, 0x00);
+    run_frame(&mut bus);
+    assert_eq!([0, 3, 1], bus.ppu.framebuffer()[3..6]);
+
+    // signed tile indices from 9000
+    bus.write(0xFF43, 0x00);
+    bus.write(0xFF42, 0x00);
+    bus.write(0xFF40, 0x81);
+    run_frame(&mut bus);
+    assert_eq!(0, bus.ppu.framebuffer()[0]);
+    for row in 0..16 {
+        let byte = bus.read(0x8010 + row);
+        bus.write(0x9010 + row, byte);
+    }
+    run_frame(&mut bus);
+    assert_eq!(3, bus.ppu.framebuffer()[0]);
+}
+
+#[test]
+fn test_window() {
+    let mut bus = memory_map(vec![0; 0x8000]);
+    load_tile(&mut bus);
+    bus.write(0xFF47, 0xE4);
+    // window from 9C00, at (20, 10)
+    bus.write(0x9C00, 0x01);
+    bus.write(0xFF4A, 10);
+    bus.write(0xFF4B, 27);
+    bus.write(0xFF40, 0xF1);
+    run_frame(&mut bus);
+
+    let frame = bus.ppu.framebuffer();
+    assert_eq!(0, frame[10 * SCREEN_WIDTH + 19]);
+    assert_eq!(3, frame[10 * SCREEN_WIDTH + 20]);
+    assert_eq!(1, frame[10 * SCREEN_WIDTH + 21]);
+    assert_eq!(0, frame[9 * SCREEN_WIDTH + 20]);
+    assert_eq!(0, frame[18 * SCREEN_WIDTH + 20]);
+}
+
+fn fifo_ppu() -> Ppu {
+    Ppu::with_renderer(Box::new(FifoRenderer::new()))
+}
+
+// A checkerboard of tiles 1 (colours 3 and 1) and 2 (colour 2) over the
+// whole background and window maps.
+fn load_scene(ppu: &mut Ppu) {
+    for row in 0..8 {
+        ppu.write(0x8010 + row * 2, 0xFF);
+        ppu.write(0x8011 + row * 2, 0x80);
+        ppu.write(0x8021 + row * 2, 0xFF);
+    }
+    for i in 0..0x800 {
+        ppu.write(0x9800 + i, 1 + ((i + i / 32) % 2) as u8);
+    }
+    ppu.write(0xFF47, 0xE4);
+}
+
+fn mode_3_length(ppu: &mut Ppu, line: u8) -> u16 {
+    while !(ppu.ly() == line && ppu.mode() == Mode::PixelTransfer) {
+        ppu.tick();
+    }
+    let mut dots = 0;
+    while ppu.mode() == Mode::PixelTransfer {
+        ppu.tick();
+        dots += 1;
+    }
+    dots
+}
+
+#[test]
+fn test_fifo_matches_scanline_for_static_scenes() {
+    for (scx, scy, wx, wy, lcdc) in [
+        (0, 0, 0, 0, 0x91),
+        (3, 200, 0, 0, 0x81),
+        (0x7D, 0x13, 50, 40, 0xF1),
+        (0xFF, 0x00, 7, 0, 0xB1),
+    ] {
+        let mut scanline = Ppu::new();
+        let mut fifo = fifo_ppu();
+        for ppu in [&mut scanline, &mut fifo] {
+            load_scene(ppu);
+            ppu.write(0xFF43, scx);
+            ppu.write(0xFF42, scy);
+            ppu.write(0xFF4B, wx);
+            ppu.write(0xFF4A, wy);
+            ppu.write(0xFF40, lcdc);
+            run_dots(ppu, 154 * 456);
+        }
+        for y in 0..SCREEN_HEIGHT {
+            let line = y * SCREEN_WIDTH..(y + 1) * SCREEN_WIDTH;
+            assert_eq!(
+                scanline.framebuffer()[line.clone()],
+                fifo.framebuffer()[line],
+                "line {} of scx {} scy {} wx {} wy {}",
+                y,
+                scx,
+                scy,
+                wx,
+                wy
+            );
+        }
+    }
+}
+
+#[test]
+fn test_fifo_mode_3_length() {
+    let mut ppu = fifo_ppu();
+    assert_eq!(172, mode_3_length(&mut ppu, 1));
+
+    // fine scroll pixels are fetched and thrown away
+    ppu.write(0xFF43, 0x03);
+    assert_eq!(175, mode_3_length(&mut ppu, 2));
+    ppu.write(0xFF43, 0x0F);
+    assert_eq!(179, mode_3_length(&mut ppu, 3));
+
+    // starting the window restarts the fetcher
+    ppu.write(0xFF43, 0x00);
+    ppu.write(0xFF4A, 0x00);
+    ppu.write(0xFF4B, 0x57);
+    ppu.write(0xFF40, 0xB1);
+    assert_eq!(178, mode_3_length(&mut ppu, 4));
+
+    // and HBlank takes up the slack
+    while ppu.mode() == Mode::HBlank {
+        ppu.tick();
+    }
+    assert_eq!((5, 0), (ppu.ly(), ppu.dot()));
+}
+
+#[test]
+fn test_fifo_mid_line_palette_change() {
+    let mut ppu = fifo_ppu();
+    load_scene(&mut ppu);
+    mode_3_length(&mut ppu, 9);
+    while !(ppu.ly() == 10 && ppu.mode() == Mode::PixelTransfer) {
+        ppu.tick();
+    }
+    // 12 dots of fetching, then one pixel per dot
+    run_dots(&mut ppu, 12 + 80);
+    ppu.write(0xFF47, 0x1B);
+    run_dots(&mut ppu, 456);
+
+    let line = &ppu.framebuffer()[10 * SCREEN_WIDTH..11 * SCREEN_WIDTH];
+    // the same tiles either side of x = 80, drawn with each palette
+    assert_eq!([2, 3, 1], [line[64], line[72], line[73]]);
+    assert_eq!([1, 1, 0, 2], [line[80], line[81], line[88], line[89]]);
+}
+
+// Tile 3 is an arrow-ish object: colour 1 on row 0 left half, colour 2 on
+// the bottom row, transparent elsewhere. Tile 4 is solid colour 3.
+// Counts the lines it's asked to draw and draws them instantly.
+struct LineCounter(Rc<Cell<u32>>);
+
+impl Renderer for LineCounter {
+    fn start_frame(&mut self) {}
+
+    fn start_line(&mut self, _state: &PpuState) {
+        self.0.set(self.0.get() + 1);
+    }
+
+    fn tick(&mut self, _state: &PpuState, _line: &mut [u8]) -> bool {
+        true
+    }
+}
+
+#[test]
+fn test_set_renderer_waits_for_the_next_frame() {
+    let mut ppu = Ppu::new();
+    let lines = Rc::new(Cell::new(0));
+    run_dots(&mut ppu, 10 * 456 + 100);
+    ppu.set_renderer(Box::new(LineCounter(lines.clone())));
+
+    run_dots(&mut ppu, 144 * 456 - 101);
+    assert_eq!(0, lines.get());
+    run_dots(&mut ppu, 101);
+    assert_eq!((0, 1), (ppu.ly(), lines.get()));
+    assert_eq!(Mode::HBlank, ppu.mode());
+}
+
+fn load_sprite_tiles(ppu: &mut Ppu) {
+    ppu.write(0x8030, 0xF0);
+    ppu.write(0x803F, 0xFF);
+    for row in 0..16 {
+        ppu.write(0x8040 + row, 0xFF);
+    }
+}
+
+fn put_sprite(ppu: &mut Ppu, index: u16, x: u8, y: u8, tile: u8, flags: u8) {
+    let entry = &mut ppu.state.oam[index as usize * 4..index as usize * 4 + 4];
+    entry.copy_from_slice(&[y + 16, x.wrapping_add(8), tile, flags]);
+}
+
+fn frame_with(mut setup: impl FnMut(&mut Ppu)) -> [Vec<u8>; 2] {
+    let mut frames = [Vec::new(), Vec::new()];
+    for (i, frame) in frames.iter_mut().enumerate() {
+        let mut ppu = if i == 0 { Ppu::new() } else { fifo_ppu() };
+        ppu.write(0xFF47, 0xE4);
+        ppu.write(0xFF48, 0xE4);
+        ppu.write(0xFF49, 0x1B);
+        ppu.write(0xFF40, 0x93);
+        load_sprite_tiles(&mut ppu);
+        setup(&mut ppu);
+        run_dots(&mut ppu, 154 * 456);
+        *frame = ppu.framebuffer().to_vec();
+    }
+    frames
+}
+
+fn pixel(frame: &[u8], x: usize, y: usize) -> u8 {
+    frame[y * SCREEN_WIDTH + x]
+}
+
+#[test]
+fn test_sprite_flips_and_palettes() {
+    for frame in frame_with(|ppu| {
+        put_sprite(ppu, 0, 10, 10, 3, 0x00);
+        put_sprite(ppu, 1, 30, 10, 3, 0x20); // x flip
+        put_sprite(ppu, 2, 50, 10, 3, 0x40); // y flip
+        put_sprite(ppu, 3, 70, 10, 3, 0x10); // OBP1
+    }) {
+        assert_eq!([1, 1, 1, 1, 0], frame[10 * SCREEN_WIDTH + 10..][..5]);
+        assert_eq!(2, pixel(&frame, 10, 17));
+        assert_eq!(
+            [0, 0, 0, 0, 1, 1, 1, 1],
+            frame[10 * SCREEN_WIDTH + 30..][..8]
+        );
+        assert_eq!(2, pixel(&frame, 50, 10));
+        assert_eq!(1, pixel(&frame, 50, 17));
+        assert_eq!(2, pixel(&frame, 70, 10));
+        assert_eq!(1, pixel(&frame, 70, 17));
+    }
+}
+
+#[test]
+fn test_tall_sprites() {
+    for frame in frame_with(|ppu| {
+        ppu.write(0xFF40, 0x97);
+        // bit 0 of the tile is ignored: tiles 4 then 5
+        put_sprite(ppu, 0, 20, 20, 5, 0x00);
+        put_sprite(ppu, 1, 40, 20, 4, 0x40);
+    }) {
+        assert_eq!(3, pixel(&frame, 20, 27));
+        assert_eq!(0, pixel(&frame, 20, 28));
+        assert_eq!(0, pixel(&frame, 40, 27));
+        assert_eq!(3, pixel(&frame, 40, 28));
+        assert_eq!(3, pixel(&frame, 40, 35));
+    }
+}
+
+#[test]
+fn test_sprite_priority() {
+    for frame in frame_with(|ppu| {
+        // lower X wins, whatever the OAM order
+        put_sprite(ppu, 0, 12, 0, 4, 0x10);
+        put_sprite(ppu, 1, 10, 0, 4, 0x00);
+        // equal X: first in OAM wins
+        put_sprite(ppu, 2, 30, 20, 4, 0x00);
+        put_sprite(ppu, 3, 30, 20, 4, 0x10);
+        // transparent pixels of the winner show the next one
+        put_sprite(ppu, 4, 50, 40, 3, 0x00);
+        put_sprite(ppu, 5, 51, 40, 4, 0x10);
+    }) {
+        assert_eq!(3, pixel(&frame, 12, 0));
+        assert_eq!(0, pixel(&frame, 18, 0));
+        assert_eq!(3, pixel(&frame, 30, 20));
+        assert_eq!(1, pixel(&frame, 51, 40));
+        assert_eq!(0, pixel(&frame, 51, 41));
+    }
+}
+
+#[test]
+fn test_sprites_behind_background() {
+    for frame in frame_with(|ppu| {
+        // background colour 1 in the top left tile
+        for row in 0..8 {
+            ppu.write(0x8010 + row * 2, 0xFF);
+        }
+        ppu.write(0x9800, 0x01);
+        put_sprite(ppu, 0, 4, 0, 4, 0x80);
+        // the hidden object still hides the one under it
+        put_sprite(ppu, 1, 5, 0, 4, 0x10);
+    }) {
+        assert_eq!([1, 1, 1, 1], frame[4..8]);
+        // but not past the background
+        assert_eq!([3, 3, 3, 3], frame[8..12]);
+        assert_eq!(0, pixel(&frame, 12, 0));
+    }
+}
+
+#[test]
+fn test_ten_sprites_per_line() {
+    for frame in frame_with(|ppu| {
+        // an off screen object still uses up a slot
+        put_sprite(ppu, 0, 0xF8, 50, 4, 0x00);
+        for i in 1..11 {
+            put_sprite(ppu, i, i as u8 * 10, 50, 4, 0x00);
+        }
+    }) {
+        assert_eq!(3, pixel(&frame, 90, 50));
+        assert_eq!(0, pixel(&frame, 100, 50));
+    }
+}
+
+#[test]
+fn test_sprite_fetch_stalls_the_fifo() {
+    let mut ppu = fifo_ppu();
+    ppu.write(0xFF40, 0x93);
+    put_sprite(&mut ppu, 0, 0, 5, 4, 0x00);
+    assert_eq!(172 + 11, mode_3_length(&mut ppu, 5));
+
+    // a second object on the same tile only costs the fetch itself
+    put_sprite(&mut ppu, 1, 0, 6, 4, 0x00);
+    put_sprite(&mut ppu, 0, 0, 6, 4, 0x00);
+    assert_eq!(172 + 11 + 6, mode_3_length(&mut ppu, 6));
+
+    // part way through a tile there's less of a wait
+    put_sprite(&mut ppu, 1, 0, 100, 4, 0x00);
+    put_sprite(&mut ppu, 0, 3, 7, 4, 0x00);
+    assert_eq!(172 + 6 + 2, mode_3_length(&mut ppu, 7));
+}
+
+// dmg-acid2 and the picture it should draw. Neither is checked in: add the
+// submodule with `git submodule add https://github.com/mattcurrie/dmg-acid2.git`
+// and build the ROM with `make -C dmg-acid2`, which needs rgbds.
+const ACID2_ROM: &str = "dmg-acid2/build/dmg-acid2.gb";
+const ACID2_REFERENCE: &str = "dmg-acid2/img/reference-dmg.png";
+
+// The reference picture as shades. It only uses the four greys 0xFF, 0xAA,
+// 0x55 and 0x00, for shades 0 to 3.
+fn acid2_reference() -> Vec<u8> {
+    let mut decoder = png::Decoder::new(File::open(ACID2_REFERENCE).unwrap());
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    assert_eq!(
+        (SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32),
+        (info.width, info.height)
+    );
+
+    let samples = reader.output_color_type().0.samples();
+    pixels[..info.buffer_size()]
+        .chunks(samples)
+        .map(|pixel| 3 - pixel[0] / 0x55)
+        .collect()
+}
+
+fn assert_draws_acid2(renderer: Box<dyn Renderer>) {
+    let mut cpu = boot_rom_file(ACID2_ROM);
+    cpu.bus.ppu = Ppu::with_renderer(renderer);
+    run_to_breakpoint(&mut cpu, 60);
+    // the picture doesn't change once it's up, so finish the frame being drawn
+    let frames = cpu.bus.ppu.frame_count();
+    while cpu.bus.ppu.frame_count() == frames {
+        cpu.step().unwrap();
+    }
+
+    let reference = acid2_reference();
+    let frame = cpu.bus.ppu.framebuffer();
+    let mismatch = frame.iter().zip(&reference).position(|(a, b)| a != b);
+    let at = mismatch.map(|i| (i % SCREEN_WIDTH, i / SCREEN_WIDTH));
+    assert_eq!(None, at, "first pixel that differs from the reference");
+}
+
+#[test]
+#[ignore = "needs the dmg-acid2 submodule built with rgbds (make -C dmg-acid2)"]
+fn test_acid2_scanline() {
+    assert_draws_acid2(Box::new(ScanlineRenderer::new()));
+}
+
+#[test]
+#[ignore = "needs the dmg-acid2 submodule built with rgbds (make -C dmg-acid2)"]
+fn test_acid2_fifo() {
+    assert_draws_acid2(Box::new(FifoRenderer::new()));
+}
@@ -0,0 +1,92 @@
+mod helpers;
+
+use std::fs;
+use std::path::PathBuf;
+
+use helpers::*;
+
+use rustyboy::bus::{Bus, MemoryMap};
+use rustyboy::cartridge::{Cartridge, VirtualClock};
+
+fn save_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("rustyboy-{}-{}.sav", name, std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn test_battery_ram_is_saved_on_drop() {
+    let path = save_path("drop");
+    let rom = build_rom(0x03, 0x04, 0x03);
+
+    let mut cartridge = Cartridge::from_rom(rom.clone()).unwrap();
+    cartridge.attach_save_file(&path).unwrap();
+    let mut bus = MemoryMap::new(cartridge.mapper);
+    bus.write(0x0000, 0x0A);
+    bus.write(0xA123, 0x42);
+    drop(bus);
+
+    let data = fs::read(&path).unwrap();
+    assert_eq!(0x8000, data.len());
+    assert_eq!(0x42, data[0x0123]);
+
+    let mut cartridge = Cartridge::from_rom(rom).unwrap();
+    cartridge.attach_save_file(&path).unwrap();
+    let mut bus = MemoryMap::new(cartridge.mapper);
+    bus.write(0x0000, 0x0A);
+    assert_eq!(0x42, bus.read(0xA123));
+
+    bus.write(0xA123, 0x43);
+    bus.cartridge.flush().unwrap();
+    assert_eq!(0x43, fs::read(&path).unwrap()[0x0123]);
+    drop(bus);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_no_battery_no_save_file() {
+    let path = save_path("no-battery");
+    let mut cartridge = Cartridge::from_rom(build_rom(0x02, 0x04, 0x03)).unwrap();
+    cartridge.attach_save_file(&path).unwrap();
+    cartridge.flush().unwrap();
+    drop(cartridge);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_rtc_footer() {
+    let path = save_path("rtc");
+    let rom = build_rom(0x10, 0x04, 0x03);
+    let clock = VirtualClock::new(1_700_000_000);
+
+    let mut cartridge =
+        Cartridge::from_rom_with_clock(rom.clone(), Box::new(clock.clone())).unwrap();
+    cartridge.attach_save_file(&path).unwrap();
+    clock.advance(125);
+    cartridge.flush().unwrap();
+
+    let data = fs::read(&path).unwrap();
+    assert_eq!(0x8000 + 48, data.len());
+    let footer = &data[0x8000..];
+    assert_eq!([5, 0, 0, 0, 2, 0, 0, 0], footer[0..8]);
+    assert_eq!(1_700_000_125u64.to_le_bytes(), footer[40..48]);
+    drop(cartridge);
+
+    // the older 44 byte footer has a 32-bit timestamp
+    let mut data = data[..0x8000 + 44].to_vec();
+    data[0x8000 + 40..].copy_from_slice(&1_700_000_000u32.to_le_bytes());
+    fs::write(&path, data).unwrap();
+    let mut cartridge = Cartridge::from_rom_with_clock(rom, Box::new(clock.clone())).unwrap();
+    cartridge.attach_save_file(&path).unwrap();
+    let mut bus = MemoryMap::new(cartridge.mapper);
+    bus.write(0x0000, 0x0A);
+    bus.write(0x6000, 0x00);
+    bus.write(0x6000, 0x01);
+    bus.write(0x4000, 0x08);
+    // 2:05 saved, plus the 125 seconds since the old timestamp
+    assert_eq!(10, bus.read(0xA000));
+    bus.write(0x4000, 0x09);
+    assert_eq!(4, bus.read(0xA000));
+    drop(bus);
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,187 @@
+mod helpers;
+
+use helpers::*;
+
+use rustyboy::bus::{Bus, MemoryMap};
+use rustyboy::cpu::new_cpu_with_bus;
+use rustyboy::interrupts::Interrupt;
+use rustyboy::serial::{link_cable, CaptureSink};
+
+fn serial_interrupt(bus: &mut MemoryMap) -> bool {
+    bus.read(0xFF0F) & Interrupt::Serial.mask() != 0
+}
+
+#[test]
+fn test_internal_clock_transfer() {
+    let mut bus = memory_map(vec![0; 0x8000]);
+    let sink = CaptureSink::new();
+    bus.serial.set_endpoint(Box::new(sink.clone()));
+    assert_eq!(0x7E, bus.read(0xFF02));
+
+    bus.write(0xFF01, 0x42);
+    bus.write(0xFF02, 0x81);
+    assert_eq!(0xFF, bus.read(0xFF02));
+    for _ in 0..1023 {
+        bus.tick();
+    }
+    assert!(!serial_interrupt(&mut bus));
+    assert!(sink.bytes().is_empty());
+
+    bus.tick();
+    assert!(serial_interrupt(&mut bus));
+    assert_eq!(0x7F, bus.read(0xFF02));
+    assert_eq!(0xFF, bus.read(0xFF01));
+    assert_eq!(vec![0x42], sink.bytes());
+}
+
+#[test]
+fn test_external_clock_without_a_cable() {
+    let mut bus = memory_map(vec![0; 0x8000]);
+    bus.write(0xFF01, 0x42);
+    bus.write(0xFF02, 0x80);
+    for _ in 0..10_000 {
+        bus.tick();
+    }
+    assert!(!serial_interrupt(&mut bus));
+    assert_eq!(0xFE, bus.read(0xFF02));
+    assert_eq!(0x42, bus.read(0xFF01));
+}
+
+#[test]
+fn test_linked_game_boys() {
+    let (left, right) = link_cable();
+    let mut master = memory_map(vec![0; 0x8000]);
+    let mut slave = memory_map(vec![0; 0x8000]);
+    master.serial.set_endpoint(Box::new(left));
+    slave.serial.set_endpoint(Box::new(right));
+
+    // nobody listening on the other end
+    master.write(0xFF01, 0x12);
+    master.write(0xFF02, 0x81);
+    for _ in 0..1024 {
+        master.tick();
+        slave.tick();
+    }
+    assert_eq!(0xFF, master.read(0xFF01));
+    assert!(!serial_interrupt(&mut slave));
+
+    master.write(0xFF0F, 0x00);
+    master.write(0xFF01, 0x12);
+    master.write(0xFF02, 0x81);
+    slave.write(0xFF01, 0x34);
+    slave.write(0xFF02, 0x80);
+    for _ in 0..1025 {
+        master.tick();
+        slave.tick();
+    }
+    assert_eq!(0x34, master.read(0xFF01));
+    assert_eq!(0x12, slave.read(0xFF01));
+    assert!(serial_interrupt(&mut master));
+    assert!(serial_interrupt(&mut slave));
+    assert_eq!(0x00, slave.read(0xFF02) & 0x80);
+}
+
+#[test]
+fn test_capturing_test_rom_output() {
+    let mut rom = vec![0; 0x8000];
+    rom[0x0100..0x0117].copy_from_slice(&[
+        0x21, 0x50, 0x01, // LD HL,0150
+        0x2A, // LD A,(HL+)
+        0xA7, // AND A
+        0x28, 0x0E, // JR Z,+14
+        0xE0, 0x01, // LDH (01),A
+        0x3E, 0x81, // LD A,81
+        0xE0, 0x02, // LDH (02),A
+        0xF0, 0x02, // LDH A,(02)
+        0xCB, 0x7F, // BIT 7,A
+        0x20, 0xFA, // JR NZ,-6
+        0x18, 0xEE, // JR -18
+        0x18, 0xFE, // JR -2
+    ]);
+    rom[0x0150..0x0154].copy_from_slice(b"ok\n\0");
+    let mut bus = memory_map(rom);
+    let sink = CaptureSink::new();
+    bus.serial.set_endpoint(Box::new(sink.clone()));
+
+    let mut cpu = new_cpu_with_bus(bus);
+    cpu.pc = 0x0100;
+    for _ in 0..10_000 {
+        if cpu.pc == 0x0115 {
+            break;
+        }
+        cpu.step().unwrap();
+    }
+    assert_eq!(0x0115, cpu.pc);
+    assert_eq!("ok\n", sink.text());
+}
+
+#[test]
+fn test_linked_transfer_cancelled_and_restarted() {
+    let (left, right) = link_cable();
+    let mut master = memory_map(vec![0; 0x8000]);
+    let mut slave = memory_map(vec![0; 0x8000]);
+    master.serial.set_endpoint(Box::new(left));
+    slave.serial.set_endpoint(Box::new(right));
+
+    slave.write(0xFF01, 0x34);
+    slave.write(0xFF02, 0x80);
+    for _ in 0..10 {
+        slave.tick();
+    }
+    slave.write(0xFF02, 0x00);
+
+    // a cancelled slave isn't listening any more
+    master.write(0xFF01, 0x12);
+    master.write(0xFF02, 0x81);
+    for _ in 0..1024 {
+        master.tick();
+        slave.tick();
+    }
+    assert_eq!(0xFF, master.read(0xFF01));
+    assert_eq!(0x34, slave.read(0xFF01));
+    assert!(!serial_interrupt(&mut slave));
+
+    // and restarting doesn't pick up the byte it missed
+    slave.write(0xFF01, 0x56);
+    slave.write(0xFF02, 0x80);
+    slave.tick();
+    assert_eq!(0xFE, slave.read(0xFF02));
+    assert!(!serial_interrupt(&mut slave));
+
+    master.write(0xFF01, 0x9A);
+    master.write(0xFF02, 0x81);
+    for _ in 0..1025 {
+        master.tick();
+        slave.tick();
+    }
+    assert_eq!(0x56, master.read(0xFF01));
+    assert_eq!(0x9A, slave.read(0xFF01));
+    assert!(serial_interrupt(&mut slave));
+}
+
+#[test]
+fn test_linked_slave_rearming_keeps_a_received_byte() {
+    let (left, right) = link_cable();
+    let mut master = memory_map(vec![0; 0x8000]);
+    let mut slave = memory_map(vec![0; 0x8000]);
+    master.serial.set_endpoint(Box::new(left));
+    slave.serial.set_endpoint(Box::new(right));
+
+    slave.write(0xFF01, 0x34);
+    slave.write(0xFF02, 0x80);
+    slave.tick();
+    master.write(0xFF01, 0x12);
+    master.write(0xFF02, 0x81);
+    for _ in 0..1024 {
+        master.tick();
+    }
+    assert_eq!(0x34, master.read(0xFF01));
+    assert!(serial_interrupt(&mut master));
+
+    // the slave writes the same SC again before it sees the byte
+    slave.write(0xFF02, 0x80);
+    slave.tick();
+    assert_eq!(0x12, slave.read(0xFF01));
+    assert_eq!(0x7E, slave.read(0xFF02));
+    assert!(serial_interrupt(&mut slave));
+}
@@ -0,0 +1,16 @@
+use rustyboy::bus::Bus;
+use rustyboy::cpu::instructions::{ArithmeticTarget16, Instruction};
+use rustyboy::cpu::new_cpu;
+
+#[test]
+fn test_pop_combines_both_bytes() {
+    // the byte at SP is the low half
+    let mut cpu = new_cpu();
+    cpu.execute(Instruction::LDrrnn(ArithmeticTarget16::SP, 0xC000));
+    cpu.bus.write(0xC000, 0x34);
+    cpu.bus.write(0xC001, 0x12);
+    cpu.execute(Instruction::POP(ArithmeticTarget16::BC));
+
+    assert_eq!(0x1234, cpu.registers.get_bc());
+    assert_eq!(0xC002, cpu.registers.sp);
+}
@@ -0,0 +1,249 @@
+mod helpers;
+
+use helpers::*;
+
+use rustyboy::bus::Bus;
+use rustyboy::cpu::new_cpu_with_bus;
+use rustyboy::interrupts::Interrupt;
+use rustyboy::timer::{Timer, DIV_ADDRESS, TAC_ADDRESS, TIMA_ADDRESS, TMA_ADDRESS};
+
+// A timer with the system counter at 0 and TAC set to `tac`.
+fn timer_with_tac(tac: u8) -> Timer {
+    let mut timer = Timer::new();
+    timer.write(DIV_ADDRESS, 0);
+    timer.write(TAC_ADDRESS, tac);
+    timer
+}
+
+fn run(timer: &mut Timer, cycles: u32) -> u8 {
+    (0..cycles).fold(0, |interrupts, _| interrupts | timer.tick())
+}
+
+#[test]
+fn test_div() {
+    let mut timer = Timer::new();
+    assert_eq!(0xAB, timer.read(DIV_ADDRESS));
+    assert_eq!(0xF8, timer.read(TAC_ADDRESS));
+
+    // any write resets the whole counter
+    timer.write(DIV_ADDRESS, 0x55);
+    assert_eq!(0, timer.counter());
+    run(&mut timer, 63);
+    assert_eq!(0, timer.read(DIV_ADDRESS));
+    run(&mut timer, 1);
+    assert_eq!(1, timer.read(DIV_ADDRESS));
+}
+
+#[test]
+fn test_tima_frequencies() {
+    for (tac, period) in [(0x04, 256), (0x05, 4), (0x06, 16), (0x07, 64)] {
+        let mut timer = timer_with_tac(tac);
+        run(&mut timer, period - 1);
+        assert_eq!(0, timer.read(TIMA_ADDRESS), "TAC {:#04X}", tac);
+        run(&mut timer, 1);
+        assert_eq!(1, timer.read(TIMA_ADDRESS), "TAC {:#04X}", tac);
+        run(&mut timer, period * 9);
+        assert_eq!(10, timer.read(TIMA_ADDRESS), "TAC {:#04X}", tac);
+    }
+
+    // disabled, nothing counts
+    let mut timer = timer_with_tac(0x01);
+    run(&mut timer, 1000);
+    assert_eq!(0, timer.read(TIMA_ADDRESS));
+}
+
+// TIMA at 0xFF with TMA 0x40, one M-cycle away from overflowing.
+fn about_to_overflow() -> Timer {
+    let mut timer = timer_with_tac(0x05);
+    timer.write(TMA_ADDRESS, 0x40);
+    timer.write(TIMA_ADDRESS, 0xFF);
+    run(&mut timer, 3);
+    timer
+}
+
+#[test]
+fn test_tima_reload_is_delayed() {
+    let mut timer = about_to_overflow();
+    assert_eq!(0, run(&mut timer, 1));
+    assert_eq!(0x00, timer.read(TIMA_ADDRESS));
+    assert_eq!(Interrupt::Timer.mask(), run(&mut timer, 1));
+    assert_eq!(0x40, timer.read(TIMA_ADDRESS));
+}
+
+#[test]
+fn test_tima_write_while_reloading() {
+    // writing TIMA in the cycle after the overflow cancels the reload
+    let mut timer = about_to_overflow();
+    run(&mut timer, 1);
+    timer.write(TIMA_ADDRESS, 0x12);
+    assert_eq!(0, run(&mut timer, 1));
+    assert_eq!(0x12, timer.read(TIMA_ADDRESS));
+
+    // in the reload cycle TIMA writes are lost and TMA writes land in both
+    let mut timer = about_to_overflow();
+    run(&mut timer, 2);
+    timer.write(TIMA_ADDRESS, 0x12);
+    assert_eq!(0x40, timer.read(TIMA_ADDRESS));
+    timer.write(TMA_ADDRESS, 0x77);
+    assert_eq!(0x77, timer.read(TIMA_ADDRESS));
+
+    // after that TIMA is writable again
+    run(&mut timer, 1);
+    timer.write(TIMA_ADDRESS, 0x12);
+    assert_eq!(0x12, timer.read(TIMA_ADDRESS));
+}
+
+#[test]
+fn test_div_write_can_increment_tima() {
+    // bit 3 of the counter is set after two M-cycles
+    let mut timer = timer_with_tac(0x05);
+    run(&mut timer, 2);
+    timer.write(DIV_ADDRESS, 0);
+    assert_eq!(1, timer.read(TIMA_ADDRESS));
+
+    // but not while it's clear
+    run(&mut timer, 1);
+    timer.write(DIV_ADDRESS, 0);
+    assert_eq!(1, timer.read(TIMA_ADDRESS));
+}
+
+#[test]
+fn test_tac_write_can_increment_tima() {
+    // disabling the timer while the selected bit is set
+    let mut timer = timer_with_tac(0x05);
+    run(&mut timer, 2);
+    timer.write(TAC_ADDRESS, 0x01);
+    assert_eq!(1, timer.read(TIMA_ADDRESS));
+
+    // switching from a set bit to a clear one
+    let mut timer = timer_with_tac(0x05);
+    run(&mut timer, 2);
+    timer.write(TAC_ADDRESS, 0x04);
+    assert_eq!(1, timer.read(TIMA_ADDRESS));
+
+    // enabling doesn't count, even with the bit set
+    let mut timer = timer_with_tac(0x01);
+    run(&mut timer, 2);
+    timer.write(TAC_ADDRESS, 0x05);
+    assert_eq!(0, timer.read(TIMA_ADDRESS));
+}
+
+#[test]
+fn test_timer_on_the_bus() {
+    let mut rom = vec![0; 0x8000];
+    rom[0x0100..0x0102].copy_from_slice(&[0x10, 0x00]); // STOP
+    let mut bus = memory_map(rom);
+    bus.write(0xFF04, 0x00);
+    bus.write(0xFF07, 0x05);
+    bus.write(0xFF06, 0x40);
+    bus.write(0xFF05, 0xFF);
+    for _ in 0..5 {
+        bus.tick();
+    }
+    assert_eq!(0x40, bus.read(0xFF05));
+    assert_ne!(0, bus.read(0xFF0F) & Interrupt::Timer.mask());
+
+    // STOP resets the divider
+    for _ in 0..1000 {
+        bus.tick();
+    }
+    assert_ne!(0, bus.read(0xFF04));
+    let mut cpu = new_cpu_with_bus(bus);
+    cpu.pc = 0x0100;
+    cpu.step().unwrap();
+    assert!(cpu.stopped);
+    assert_eq!(0, cpu.bus.read(0xFF04));
+}
+
+// Runs one of mooneye-test-suite's timer ROMs. A passing ROM leaves the
+// Fibonacci numbers in B, C, D, E, H and L when it hits the breakpoint. The
+// ROMs aren't checked in: add the submodule with
+// `git submodule add https://github.com/Gekkio/mooneye-test-suite.git` and
+// build them with `make -C mooneye-test-suite`, which needs rgbds.
+fn assert_mooneye_passes(name: &str) {
+    let mut cpu = boot_rom_file(&format!(
+        "mooneye-test-suite/build/acceptance/timer/{}.gb",
+        name
+    ));
+    run_to_breakpoint(&mut cpu, 120);
+    let r = &cpu.registers;
+    assert_eq!([3, 5, 8, 13, 21, 34], [r.b, r.c, r.d, r.e, r.h, r.l]);
+}
+
+#[test]
+#[ignore = "needs the mooneye-test-suite submodule built with rgbds (make -C mooneye-test-suite)"]
+fn test_mooneye_div_write() {
+    assert_mooneye_passes("div_write");
+}
+
+#[test]
+#[ignore = "needs the mooneye-test-suite submodule built with rgbds (make -C mooneye-test-suite)"]
+fn test_mooneye_rapid_toggle() {
+    assert_mooneye_passes("rapid_toggle");
+}
+
+#[test]
+#[ignore = "needs the mooneye-test-suite submodule built with rgbds (make -C mooneye-test-suite)"]
+fn test_mooneye_tim00() {
+    assert_mooneye_passes("tim00");
+}
+
+#[test]
+#[ignore = "needs the mooneye-test-suite submodule built with rgbds (make -C mooneye-test-suite)"]
+fn test_mooneye_tim00_div_trigger() {
+    assert_mooneye_passes("tim00_div_trigger");
+}
+
+#[test]
+#[ignore = "needs the mooneye-test-suite submodule built with rgbds (make -C mooneye-test-suite)"]
+fn test_mooneye_tim01() {
+    assert_mooneye_passes("tim01");
+}
+
+#[test]
+#[ignore = "needs the mooneye-test-suite submodule built with rgbds (make -C mooneye-test-suite)"]
+fn test_mooneye_tim01_div_trigger() {
+    assert_mooneye_passes("tim01_div_trigger");
+}
+
+#[test]
+#[ignore = "needs the mooneye-test-suite submodule built with rgbds (make -C mooneye-test-suite)"]
+fn test_mooneye_tim10() {
+    assert_mooneye_passes("tim10");
+}
+
+#[test]
+#[ignore = "needs the mooneye-test-suite submodule built with rgbds (make -C mooneye-test-suite)"]
+fn test_mooneye_tim10_div_trigger() {
+    assert_mooneye_passes("tim10_div_trigger");
+}
+
+#[test]
+#[ignore = "needs the mooneye-test-suite submodule built with rgbds (make -C mooneye-test-suite)"]
+fn test_mooneye_tim11() {
+    assert_mooneye_passes("tim11");
+}
+
+#[test]
+#[ignore = "needs the mooneye-test-suite submodule built with rgbds (make -C mooneye-test-suite)"]
+fn test_mooneye_tim11_div_trigger() {
+    assert_mooneye_passes("tim11_div_trigger");
+}
+
+#[test]
+#[ignore = "needs the mooneye-test-suite submodule built with rgbds (make -C mooneye-test-suite)"]
+fn test_mooneye_tima_reload() {
+    assert_mooneye_passes("tima_reload");
+}
+
+#[test]
+#[ignore = "needs the mooneye-test-suite submodule built with rgbds (make -C mooneye-test-suite)"]
+fn test_mooneye_tima_write_reloading() {
+    assert_mooneye_passes("tima_write_reloading");
+}
+
+#[test]
+#[ignore = "needs the mooneye-test-suite submodule built with rgbds (make -C mooneye-test-suite)"]
+fn test_mooneye_tma_write_reloading() {
+    assert_mooneye_passes("tma_write_reloading");
+}
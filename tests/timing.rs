@@ -0,0 +1,149 @@
+use rustyboy::bus::{Bus, FlatRam};
+use rustyboy::cpu::decoder::{decode, PREFIX_CB};
+use rustyboy::cpu::new_cpu_with_bus;
+use rustyboy::cpu::registers::FlagsRegister;
+use rustyboy::cpu::timing::cycles;
+
+// M-cycles per opcode from the published opcode tables, with conditional
+// branches not taken. Illegal opcodes and the 0xCB prefix are 0.
+#[rustfmt::skip]
+const UNPREFIXED: [u8; 256] = [
+//  0  1  2  3  4  5  6  7  8  9  A  B  C  D  E  F
+    1, 3, 2, 2, 1, 1, 2, 1, 5, 2, 2, 2, 1, 1, 2, 1, // 0x
+    1, 3, 2, 2, 1, 1, 2, 1, 3, 2, 2, 2, 1, 1, 2, 1, // 1x
+    2, 3, 2, 2, 1, 1, 2, 1, 2, 2, 2, 2, 1, 1, 2, 1, // 2x
+    2, 3, 2, 2, 3, 3, 3, 1, 2, 2, 2, 2, 1, 1, 2, 1, // 3x
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1, // 4x
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1, // 5x
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1, // 6x
+    2, 2, 2, 2, 2, 2, 1, 2, 1, 1, 1, 1, 1, 1, 2, 1, // 7x
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1, // 8x
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1, // 9x
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1, // Ax
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1, // Bx
+    2, 3, 3, 4, 3, 4, 2, 4, 2, 4, 3, 0, 3, 6, 2, 4, // Cx
+    2, 3, 3, 0, 3, 4, 2, 4, 2, 4, 3, 0, 3, 0, 2, 4, // Dx
+    3, 3, 2, 0, 0, 4, 2, 4, 4, 1, 4, 0, 0, 0, 2, 4, // Ex
+    3, 3, 2, 1, 0, 4, 2, 4, 3, 2, 4, 1, 0, 0, 2, 4, // Fx
+];
+
+// Conditional branches when they are taken.
+const TAKEN: [(u8, u8); 16] = [
+    (0x20, 3),
+    (0x28, 3),
+    (0x30, 3),
+    (0x38, 3),
+    (0xC0, 5),
+    (0xC8, 5),
+    (0xD0, 5),
+    (0xD8, 5),
+    (0xC2, 4),
+    (0xCA, 4),
+    (0xD2, 4),
+    (0xDA, 4),
+    (0xC4, 6),
+    (0xCC, 6),
+    (0xD4, 6),
+    (0xDC, 6),
+];
+
+// 0xCB-prefixed opcodes take 2 M-cycles on a register, 4 on (HL) and 3 for
+// BIT n,(HL).
+fn prefixed(opcode: u8) -> u8 {
+    match (opcode & 0x07, opcode >> 6) {
+        (6, 1) => 3,
+        (6, _) => 4,
+        _ => 2,
+    }
+}
+
+#[test]
+fn test_cycles_match_published_tables() {
+    for opcode in 0..=0xFFu8 {
+        if let Ok((instruction, _)) = decode(&[opcode, 0, 0]) {
+            if opcode != PREFIX_CB {
+                assert_eq!(
+                    UNPREFIXED[opcode as usize],
+                    cycles(&instruction, false),
+                    "{:#04X}",
+                    opcode
+                );
+            }
+        }
+
+        let (instruction, _) = decode(&[PREFIX_CB, opcode]).unwrap();
+        assert_eq!(
+            prefixed(opcode),
+            cycles(&instruction, false),
+            "CB {:#04X}",
+            opcode
+        );
+    }
+
+    for (opcode, taken) in TAKEN {
+        let (instruction, _) = decode(&[opcode, 0, 0]).unwrap();
+        assert_eq!(taken, cycles(&instruction, true), "{:#04X}", opcode);
+    }
+}
+
+struct CountingBus {
+    ram: FlatRam,
+    ticks: u32,
+    accesses: u32,
+}
+
+// IE and IF are looked at for free, everything else costs a cycle
+fn costs_cycle(address: u16) -> bool {
+    address != 0xFFFF && address != 0xFF0F
+}
+
+impl Bus for CountingBus {
+    fn read(&mut self, address: u16) -> u8 {
+        self.accesses += costs_cycle(address) as u32;
+        self.ram.read(address)
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        self.accesses += costs_cycle(address) as u32;
+        self.ram.write(address, value);
+    }
+
+    fn tick(&mut self) {
+        self.ticks += 1;
+    }
+}
+
+fn run_one(bytes: &[u8], flags: u8) -> (u8, u32, u32) {
+    let mut ram = FlatRam::new();
+    ram.load(0x0100, bytes);
+    let mut cpu = new_cpu_with_bus(CountingBus {
+        ram,
+        ticks: 0,
+        accesses: 0,
+    });
+    cpu.pc = 0x0100;
+    cpu.registers.sp = 0xD000;
+    cpu.registers.set_hl(0xC000);
+    cpu.registers.f = FlagsRegister::from(flags);
+
+    let cycles = cpu.step().unwrap();
+    (cycles, cpu.bus.ticks, cpu.bus.accesses)
+}
+
+#[test]
+fn test_every_instruction_ticks_the_bus_once_per_cycle() {
+    let mut programs: Vec<Vec<u8>> = (0..=0xFFu8)
+        .filter(|opcode| decode(&[*opcode, 0, 0]).is_ok() && *opcode != PREFIX_CB)
+        .map(|opcode| vec![opcode, 0x01, 0xC0])
+        .collect();
+    programs.extend((0..=0xFFu8).map(|opcode| vec![PREFIX_CB, opcode]));
+
+    for program in programs {
+        // all flags clear and all flags set cover both sides of every branch
+        for flags in [0x00, 0xF0] {
+            let (cycles, ticks, accesses) = run_one(&program, flags);
+            assert_eq!(cycles as u32, ticks, "{:02X?}", program);
+            assert!(accesses <= ticks, "{:02X?}", program);
+        }
+    }
+}